@@ -1,12 +1,140 @@
-use std::sync::Arc;
+use std::{mem::discriminant, ops::Range, sync::Arc};
 
-use egui::{mutex::RwLock, Color32, RichText, ScrollArea, Widget};
+use egui::{mutex::RwLock, Color32, DragValue, RichText, ScrollArea, TextEdit, Widget};
+use serde::{Deserialize, Serialize};
 
-use super::hex_input;
+use super::{expression::{Expr, ExpressionContext}, hex_input, labels::Label};
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum BreakpointKind {
+    /// Instruction at `addr` about to execute.
+    #[default]
+    Address,
+    /// Crystal tick reached.
+    Tick(u64),
+    /// Frame number reached.
+    Frame(u64),
+}
+
+impl BreakpointKind {
+    fn label(&self) -> &'static str {
+        match self {
+            BreakpointKind::Address => "PC",
+            BreakpointKind::Tick(_) => "Tick",
+            BreakpointKind::Frame(_) => "Frame",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub enabled: bool,
+    pub addr: u16,
+    #[serde(default)]
+    pub kind: BreakpointKind,
+    condition: String,
+    #[serde(skip, default = "no_expression")]
+    expression: Result<Option<Expr>, String>,
+    #[serde(skip)]
+    pub hits: u32,
+    /// Number of first hits that don't stop or log.
+    pub ignore: u32,
+    /// Logpoints print `message` to the log pane instead of pausing.
+    pub logpoint: bool,
+    pub message: String,
+    #[serde(default)]
+    pub group: String,
+    /// One-shot breakpoints delete themselves once they fired.
+    #[serde(default)]
+    pub temporary: bool,
+}
+
+fn no_expression() -> Result<Option<Expr>, String> {
+    Ok(None)
+}
+
+impl Breakpoint {
+    pub fn new(kind: BreakpointKind, addr: u16, group: String, temporary: bool) -> Self {
+        Self {
+            enabled: true,
+            addr,
+            kind,
+            condition: String::new(),
+            expression: no_expression(),
+            hits: 0,
+            ignore: 0,
+            logpoint: false,
+            message: String::new(),
+            group,
+            temporary,
+        }
+    }
+
+    pub fn condition(&self) -> &str {
+        &self.condition
+    }
+
+    /// Parsed against `labels`, to be set again when they change.
+    pub fn set_condition(&mut self, condition: String, labels: &[Label]) {
+        self.expression = match condition.trim() {
+            "" => Ok(None),
+            c => Expr::parse(c, labels).map(Some),
+        };
+        self.condition = condition;
+    }
+
+    /// `pc` is set on a new instruction, `ticks` and `frames` are the ones elapsed since the last check.
+    /// An invalid condition still breaks, so that a typo doesn't silently skip the breakpoint.
+    pub fn matches(&self, pc: Option<u16>, ticks: Range<u64>, frames: Range<u64>, ctx: &impl ExpressionContext) -> bool {
+        let triggered = match self.kind {
+            BreakpointKind::Address => pc == Some(self.addr),
+            BreakpointKind::Tick(tick) => ticks.contains(&tick),
+            BreakpointKind::Frame(frame) => frames.contains(&frame),
+        };
+        self.enabled && triggered && match &self.expression {
+            Ok(Some(e)) => e.eval(ctx) != 0,
+            _ => true,
+        }
+    }
+}
+
+/// Replaces each `{expr}` of a logpoint message with its value, `{m:addr}` being a shorthand for `{[addr]}`.
+/// Values are shown in hexadecimal with the width of the expression, negative ones with a sign.
+pub fn format_message(message: &str, labels: &[Label], ctx: &impl ExpressionContext) -> String {
+    let mut out = String::new();
+    let mut rest = message;
+
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..start + len];
+        let expression = match placeholder.strip_prefix("m:") {
+            Some(addr) => format!("[{}]", addr),
+            None => placeholder.to_string(),
+        };
+        match Expr::parse(&expression, labels) {
+            Err(_) => out.push_str("{?}"),
+            Ok(e) => {
+                let (v, digits) = (e.eval(ctx), e.hex_digits());
+                let sign = if v < 0 { "-" } else { "" };
+                out.push_str(&format!("{}${:0digits$X}", sign, v.unsigned_abs()));
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
 
 pub struct Breakpoints {
     input: u16,
-    buffer: Arc<RwLock<String>>
+    buffer: Arc<RwLock<String>>,
+    group: String,
+    temporary: bool,
+    kind: BreakpointKind,
+    target: u64,
 }
 
 impl Breakpoints {
@@ -14,15 +142,72 @@ impl Breakpoints {
         Self {
             input: 0,
             buffer: Default::default(),
+            group: String::new(),
+            temporary: false,
+            kind: BreakpointKind::Address,
+            target: 0,
         }
     }
 
-    pub fn show_ui(&mut self, ui: &mut egui::Ui, bps: &mut Vec<(bool, u16)>) {
+    /// Returns true when the breakpoints changed, text and drag edits only once they're done.
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, bps: &mut Vec<Breakpoint>, labels: &[Label]) -> bool {
+        let mut changed = false;
+
         ui.horizontal(|ui| {
             ui.label(RichText::new("Breakpoints").strong());
-            hex_input::HexInput::new(&mut self.input, self.buffer.clone(), 4).ui(ui);
+            egui::ComboBox::from_id_source("breakpoint_kind")
+                .selected_text(self.kind.label())
+                .width(50.0)
+                .show_ui(ui, |ui| {
+                    for kind in [BreakpointKind::Address, BreakpointKind::Tick(0), BreakpointKind::Frame(0)] {
+                        if ui.selectable_label(discriminant(&self.kind) == discriminant(&kind), kind.label()).clicked() {
+                            self.kind = kind;
+                        }
+                    }
+                });
+            match self.kind {
+                BreakpointKind::Address => { hex_input::HexInput::new(&mut self.input, self.buffer.clone(), 4).ui(ui); }
+                _ => { ui.add(DragValue::new(&mut self.target)); }
+            }
+            ui.add(TextEdit::singleline(&mut self.group).hint_text("group").desired_width(60.0));
+            ui.checkbox(&mut self.temporary, "once")
+                .on_hover_text("Delete the breakpoint once it fired");
             if ui.button("Add").clicked() {
-                bps.push((true, self.input));
+                let kind = match self.kind {
+                    BreakpointKind::Address => BreakpointKind::Address,
+                    BreakpointKind::Tick(_) => BreakpointKind::Tick(self.target),
+                    BreakpointKind::Frame(_) => BreakpointKind::Frame(self.target),
+                };
+                bps.push(Breakpoint::new(kind, self.input, self.group.trim().to_string(), self.temporary));
+                changed = true;
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("Enable all").clicked() {
+                bps.iter_mut().for_each(|bp| bp.enabled = true);
+                changed = true;
+            }
+            if ui.button("Disable all").clicked() {
+                bps.iter_mut().for_each(|bp| bp.enabled = false);
+                changed = true;
+            }
+            if ui.button("Clear all").clicked() {
+                bps.clear();
+                changed = true;
+            }
+
+            let mut groups: Vec<String> = bps.iter().map(|bp| bp.group.clone()).filter(|g| !g.is_empty()).collect();
+            groups.sort();
+            groups.dedup();
+            for group in groups {
+                let enabled = bps.iter().any(|bp| bp.group == group && bp.enabled);
+                if ui.selectable_label(enabled, group.as_str())
+                        .on_hover_text("Enable or disable the group")
+                        .clicked() {
+                    bps.iter_mut().filter(|bp| bp.group == group).for_each(|bp| bp.enabled = !enabled);
+                    changed = true;
+                }
             }
         });
 
@@ -34,7 +219,6 @@ impl Breakpoints {
         let row_height = ui.text_style_height(&egui::TextStyle::Body);
 
         let mut to_delete: Option<usize> = None;
-        let mut switch_status: Option<usize> = None;
 
         scroll.show_rows(ui, row_height, bps.len(), |ui, line_range| {
             egui::Grid::new("breakpoint_grid")
@@ -44,20 +228,57 @@ impl Breakpoints {
                     let mut current_line = line_range.start;
 
                     while current_line != line_range.end {
-                        let (en, addr) = bps[current_line];
+                        let bp = &mut bps[current_line];
+
+                        let color = if bp.logpoint {Color32::YELLOW} else {Color32::RED};
+                        let icon = if bp.enabled {RichText::new("⏺").color(color)} else {RichText::new("○")};
 
-                        let icon = if en {RichText::new("⏺").color(Color32::RED)} else {RichText::new("○")};
-                        
                         if ui.add(egui::Button::new(icon).frame(false)).clicked() {
-                            switch_status = Some(current_line);
+                            bp.enabled = !bp.enabled;
+                            changed = true;
                         }
-                        
-                        ui.monospace(format!("${:04X}", addr));
-                        
+
+                        ui.monospace(match bp.kind {
+                            BreakpointKind::Address => format!("${:04X}", bp.addr),
+                            BreakpointKind::Tick(tick) => format!("tick {}", tick),
+                            BreakpointKind::Frame(frame) => format!("frame {}", frame),
+                        });
+                        ui.label(RichText::new(format!("{}{}", bp.group, if bp.temporary {" once"} else {""})).weak());
+
+                        if ui.add(egui::Button::new(RichText::new(format!("{}", bp.hits)).monospace()).frame(false))
+                                .on_hover_text("Hits, click to reset")
+                                .clicked() {
+                            bp.hits = 0;
+                        }
+                        let response = ui.add(DragValue::new(&mut bp.ignore).prefix("ignore "));
+                        changed |= response.drag_stopped() || response.lost_focus();
+
+                        let mut condition = bp.condition().to_string();
+                        let error = bp.expression.as_ref().err();
+                        let mut edit = TextEdit::singleline(&mut condition)
+                            .hint_text("condition")
+                            .desired_width(120.0);
+                        if error.is_some() {
+                            edit = edit.text_color(Color32::RED);
+                        }
+                        let mut response = ui.add(edit);
+                        if let Some(e) = error {
+                            response = response.on_hover_text(e);
+                        }
+                        if response.changed() {
+                            bp.set_condition(condition, labels);
+                        }
+                        changed |= response.lost_focus();
+
+                        changed |= ui.checkbox(&mut bp.logpoint, "log").changed();
+                        changed |= ui.add_enabled(bp.logpoint, TextEdit::singleline(&mut bp.message)
+                            .hint_text("PC={pc} A={a} [$80]={m:$80}")
+                            .desired_width(160.0)).lost_focus();
+
                         if ui.add(egui::Button::new("❌").frame(false)).clicked() {
                             to_delete = Some(current_line);
-                        }  
-                        
+                        }
+
                         ui.end_row();
                         current_line += 1;
                     }
@@ -66,11 +287,60 @@ impl Breakpoints {
 
         if let Some(d) = to_delete {
             bps.remove(d);
+            changed = true;
         }
 
-        if switch_status.is_some() {
-            let i = switch_status.unwrap();
-            bps[i].0 = !bps[i].0; 
-        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debugger::expression::tests::TestContext;
+
+    fn context() -> TestContext {
+        let mut ctx = TestContext::new();
+        ctx.memory[0x2080] = 0x80;
+        ctx.memory[0x2081] = 0x81;
+        ctx
+    }
+
+    #[test]
+    fn invalid_condition_breaks() {
+        let ctx = context();
+        let mut bp = Breakpoint::new(BreakpointKind::Address, 0x0200, String::new(), false);
+        bp.set_condition("typo == 1".to_string(), &[]);
+        assert!(bp.expression.is_err());
+        assert!(bp.matches(Some(0x0200), 0..0, 0..0, &ctx));
+        bp.set_condition("A == 0".to_string(), &[]);
+        assert!(!bp.matches(Some(0x0200), 0..0, 0..0, &ctx));
+        let labels = [Label { name: "typo".to_string(), addr: 1 }];
+        bp.set_condition("typo == 1".to_string(), &labels);
+        assert!(bp.expression.is_ok());
+    }
+
+    #[test]
+    fn format_message_values() {
+        let ctx = context();
+        assert_eq!(format_message("A={A} PC={PC}", &[], &ctx), "A=$12 PC=$0250");
+        assert_eq!(format_message("{m:$2080} {[$2081]}", &[], &ctx), "$80 $81");
+        assert_eq!(format_message("{A + 1} {word($2080)} {$2080}", &[], &ctx), "$13 $8180 $2080");
+        assert_eq!(format_message("{A * $100} {A == $12}", &[], &ctx), "$1200 $01");
+    }
+
+    #[test]
+    fn format_message_negative() {
+        let ctx = context();
+        assert_eq!(format_message("{Y - 3} {-[$2080]}", &[], &ctx), "-$01 -$80");
+        assert_eq!(format_message("{PC - $260}", &[], &ctx), "-$0010");
+    }
+
+    #[test]
+    fn format_message_text() {
+        let ctx = context();
+        assert_eq!(format_message("no values", &[], &ctx), "no values");
+        assert_eq!(format_message("bad {A +} end", &[], &ctx), "bad {?} end");
+        assert_eq!(format_message("open {A", &[], &ctx), "open {A");
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,10 @@
-use eframe::egui::{self,  ScrollArea, TextStyle, TextWrapMode, Ui, Vec2};
-use holani::{mikey::cpu::M6502, lynx::Lynx};
+use eframe::egui::{self, Color32, Label, RichText, ScrollArea, Sense, TextStyle, TextWrapMode, Ui, Vec2};
+use std::sync::LazyLock;
+use holani::lynx::Lynx;
+use serde::{Deserialize, Serialize};
+use super::{guards::AddressSet, labels};
 
-macro_rules! get_word {
-    ($lynx: ident, $addr: expr) => {
-        ($lynx.cpu_mem($addr) as u16) | (($lynx.cpu_mem($addr+1) as u16) << 8)
-    };
-}
+static KNOWN_ADDRS: LazyLock<Vec<Option<String>>> = LazyLock::new(DisasmWidget::known_addresses);
 
 macro_rules! known_addr {
     ($addrs: ident, $addr: ident, $fnone: expr, $fvalue: expr) => {
@@ -47,59 +46,114 @@ enum AddressingMode
     Ind
 }
 
-#[allow(dead_code)]
-fn get_opcode_target(entry: &DisasmToken, cpu: &M6502, lynx: &Lynx) -> i16
-{
-    let mut addr = entry.base_address;
-    let mut operand: u16 = 0;
-    let count = next_address(lynx, addr) - addr;
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
 
-    let opcode = lynx.cpu_mem(addr);
+impl Access {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Access::Read => "R",
+            Access::Write => "W",
+            Access::ReadWrite => "RW",
+        }
+    }
+}
 
-    match count {
-        3 => {
-            addr += 1;
-            operand = lynx.cpu_mem(addr) as u16;
-            addr += 1;
-            operand += (lynx.cpu_mem(addr) as u16) << 8;
-            addr += 1;
+#[derive(Clone, Copy)]
+pub struct MemoryAccess {
+    pub addr: u16,
+    /// Bytes accessed from `addr`, 2 for a pointer or a return address.
+    pub len: u16,
+    pub access: Access,
+}
+
+impl MemoryAccess {
+    pub fn addresses(&self) -> impl Iterator<Item = u16> {
+        let addr = self.addr;
+        (0..self.len).map(move |i| addr.wrapping_add(i))
+    }
+
+    /// Adds the byte at `addr`, extending the previous access when it follows it.
+    fn push(accesses: &mut Vec<MemoryAccess>, addr: u16, access: Access) {
+        match accesses.last_mut() {
+            Some(last) if last.access == access && last.addr.wrapping_add(last.len) == addr => last.len += 1,
+            _ => accesses.push(MemoryAccess { addr, len: 1, access }),
         }
-        2 => {
-            addr += 1;
-            operand = lynx.cpu_mem(addr) as u16;
-            addr += 1;
+    }
+}
+
+/// Memory accesses the instruction at `pc` is about to make, from the current registers.
+/// The data access comes first, followed by the pointer bytes read and the stack bytes pushed or pulled.
+/// Jump targets aren't reported.
+pub fn memory_accesses(lynx: &Lynx, pc: u16) -> Vec<MemoryAccess> {
+    let cpu = lynx.mikey().cpu();
+    let (mnemonic, mode) = INSTRUCTIONS[lynx.cpu_mem(pc) as usize];
+    let zp = lynx.cpu_mem(pc.wrapping_add(1));
+    let abs = (lynx.cpu_mem(pc.wrapping_add(1)) as u16) | ((lynx.cpu_mem(pc.wrapping_add(2)) as u16) << 8);
+    let zp_word = |zp: u8| (lynx.cpu_mem(zp as u16) as u16) | ((lynx.cpu_mem(zp.wrapping_add(1) as u16) as u16) << 8);
+    let mut accesses = vec![];
+
+    let (addr, pointer) = match mode {
+        AddressingMode::Zp | AddressingMode::Zrel => (Some(zp as u16), None),
+        AddressingMode::Zpx => (Some(zp.wrapping_add(cpu.x()) as u16), None),
+        AddressingMode::Zpy => (Some(zp.wrapping_add(cpu.y()) as u16), None),
+        AddressingMode::Absl => (Some(abs), None),
+        AddressingMode::Absx => (Some(abs.wrapping_add(cpu.x() as u16)), None),
+        AddressingMode::Absy => (Some(abs.wrapping_add(cpu.y() as u16)), None),
+        AddressingMode::Ind => (Some(zp_word(zp)), Some(zp)),
+        AddressingMode::Indx => (Some(zp_word(zp.wrapping_add(cpu.x()))), Some(zp.wrapping_add(cpu.x()))),
+        AddressingMode::Indy => (Some(zp_word(zp).wrapping_add(cpu.y() as u16)), Some(zp)),
+        // `JMP (abs)` and `JMP (abs,X)` only read their pointer.
+        AddressingMode::Iabs | AddressingMode::Iabsx => {
+            let ptr = if mode == AddressingMode::Iabsx { abs.wrapping_add(cpu.x() as u16) } else { abs };
+            MemoryAccess::push(&mut accesses, ptr, Access::Read);
+            MemoryAccess::push(&mut accesses, ptr.wrapping_add(1), Access::Read);
+            (None, None)
         }
-        _ => {
-            addr += 1;
+        AddressingMode::Accu
+        | AddressingMode::Imm
+        | AddressingMode::Implied
+        | AddressingMode::Rel
+        | AddressingMode::Illegal => (None, None),
+    };
+
+    if let Some(addr) = addr {
+        let access = match mnemonic {
+            "JMP" | "JSR" => None,
+            "STA" | "STX" | "STY" | "STZ" => Some(Access::Write),
+            "ASL" | "LSR" | "ROL" | "ROR" | "INC" | "DEC" | "TSB" | "TRB" => Some(Access::ReadWrite),
+            m if m.starts_with("RMB") || m.starts_with("SMB") => Some(Access::ReadWrite),
+            _ => Some(Access::Read),
+        };
+        if let Some(access) = access {
+            accesses.push(MemoryAccess { addr, len: 1, access });
         }
     }
+    if let Some(ptr) = pointer {
+        MemoryAccess::push(&mut accesses, ptr as u16, Access::Read);
+        MemoryAccess::push(&mut accesses, ptr.wrapping_add(1) as u16, Access::Read);
+    }
 
-    match INSTRUCTIONS[opcode as usize].1 {
-        AddressingMode::Accu => -1,
-        AddressingMode::Imm => -1,
-        AddressingMode::Absl => lynx.cpu_mem(operand) as i16,
-        AddressingMode::Rel => {
-            let scrap: u16 = if operand > 128 {
-                (-128 + (operand & 0x7f) as i16) as u16
-            } else {
-                operand
-            };
-            lynx.cpu_mem(addr + scrap) as i16
-        }
-        AddressingMode::Iabs => lynx.cpu_mem(get_word!(lynx, operand)) as i16,
-        AddressingMode::Ind => lynx.cpu_mem(lynx.cpu_mem(operand) as u16) as i16,
-        AddressingMode::Zp => lynx.cpu_mem(operand) as i16,
-        AddressingMode::Zpx => lynx.cpu_mem(operand + cpu.x() as u16) as i16,
-        AddressingMode::Zpy => lynx.cpu_mem(operand + cpu.y() as u16) as i16,
-        AddressingMode::Absx => lynx.cpu_mem(operand + cpu.x() as u16) as i16,
-        AddressingMode::Absy => lynx.cpu_mem(operand + cpu.y() as u16) as i16,
-        AddressingMode::Iabsx => lynx.cpu_mem(get_word!(lynx, operand + cpu.x() as u16)) as i16,
-        AddressingMode::Zrel => -1,
-        AddressingMode::Indx => lynx.cpu_mem(get_word!(lynx, operand) + cpu.x() as u16) as i16,
-        AddressingMode::Indy => lynx.cpu_mem(get_word!(lynx, operand) + cpu.y() as u16) as i16,
-        AddressingMode::Implied => -1,
-        AddressingMode::Illegal => -1,
-    } 
+    // Pushes write below S, pulls read above it, both wrapping within page 1.
+    let s = cpu.s();
+    let (access, first, len) = match mnemonic {
+        "PHA" | "PHX" | "PHY" | "PHP" => (Access::Write, s, 1),
+        "JSR" => (Access::Write, s.wrapping_sub(1), 2),
+        "BRK" => (Access::Write, s.wrapping_sub(2), 3),
+        "PLA" | "PLX" | "PLY" | "PLP" => (Access::Read, s.wrapping_add(1), 1),
+        "RTS" => (Access::Read, s.wrapping_add(1), 2),
+        "RTI" => (Access::Read, s.wrapping_add(1), 3),
+        _ => return accesses,
+    };
+    for i in 0..len {
+        MemoryAccess::push(&mut accesses, 0x0100 | first.wrapping_add(i) as u16, access);
+    }
+
+    accesses
 }
 
 fn disassemble(lynx: &Lynx, mut addr: u16, known: &[Option<String>]) -> (DisasmToken, u16) {
@@ -175,7 +229,25 @@ fn disassemble(lynx: &Lynx, mut addr: u16, known: &[Option<String>]) -> (DisasmT
     (ret, addr)
 }
 
-fn next_address(lynx: &Lynx, addr: u16) -> u16 {
+/// True for the `NOP..` entries of the opcode table.
+pub fn is_illegal(opcode: u8) -> bool {
+    INSTRUCTIONS[opcode as usize].1 == AddressingMode::Illegal
+}
+
+/// Hardware register names the CPU can access, by address.
+pub fn hardware_registers() -> impl Iterator<Item = (u16, &'static str)> {
+    KNOWN_ADDRS
+        .iter()
+        .enumerate()
+        .filter_map(|(addr, name)| name.as_deref().map(|n| (addr as u16, n)))
+}
+
+/// Address of the hardware register called `name`.
+pub fn hardware_register(name: &str) -> Option<u16> {
+    KNOWN_ADDRS.iter().position(|n| n.as_deref() == Some(name)).map(|addr| addr as u16)
+}
+
+pub fn next_address(lynx: &Lynx, addr: u16) -> u16 {
     let data: u8 = lynx.cpu_mem(addr);
     let operand = INSTRUCTIONS[data as usize].1;
     addr.overflowing_add(OP_LENGTH[operand as usize]).0
@@ -205,20 +277,25 @@ pub struct DisasmWidget {
     options: DisasmWidgetOptions,
     visible_start_address: u16,
     frame_data: BetweenFrameData,
-    known_addrs: Vec<Option<String>>
+    /// Hardware registers overlaid with the user labels.
+    known_addrs: Vec<Option<String>>,
 }
 
 impl DisasmWidget {
     pub fn new() -> Self {
-        const NONE: Option<String> = None;
-        let mut s = Self {
+        Self {
             options: Default::default(),
             visible_start_address: 0,
             frame_data: Default::default(),
-            known_addrs: vec![NONE; 0xffff+1]
-        };
-        s.initialize_known_addresses();
-        s
+            known_addrs: KNOWN_ADDRS.clone(),
+        }
+    }
+
+    pub fn set_labels(&mut self, labels: &[labels::Label]) {
+        self.known_addrs = KNOWN_ADDRS.clone();
+        for label in labels {
+            self.known_addrs[label.addr as usize] = Some(label.name.clone());
+        }
     }
 
     fn draw_options_area(&mut self, ui: &mut Ui) {
@@ -232,7 +309,9 @@ impl DisasmWidget {
     fn draw_main_options(&mut self, _ui: &mut Ui) {
     }
 
-    pub fn disasm_show(&mut self, ui: &mut Ui, pc: u16, lynx: &Lynx) {
+    /// Returns the address picked with "Run to here", if any.
+    /// Instructions with a byte in `modified` are highlighted as overwritten code.
+    pub fn disasm_show(&mut self, ui: &mut Ui, pc: u16, lynx: &Lynx, modified: &AddressSet) -> Option<u16> {
         self.draw_options_area(ui);
 
         ui.separator();
@@ -245,6 +324,7 @@ impl DisasmWidget {
             .auto_shrink([false, true]);
 
         let mut working_pc = pc;
+        let mut run_to: Option<u16> = None;
 
         scroll.show_rows(ui, line_height, max_lines, |ui, line_range| {
             self.visible_start_address = line_range.start as u16;
@@ -263,10 +343,28 @@ impl DisasmWidget {
                     while current_line != line_range.end {
                         (token, working_pc) = disassemble(lynx, working_pc, &self.known_addrs);
 
-                        ui.label(format!("{:04X}", token.base_address));
-                        ui.label(token.data);
-                        ui.label(token.opcode);
-                        ui.label(token.operands);
+                        let address = token.base_address;
+                        let is_modified = (0..working_pc.wrapping_sub(address)).any(|i| modified.contains(address.wrapping_add(i)));
+                        let color = if is_modified { Color32::ORANGE } else { ui.visuals().text_color() };
+                        let cells = [
+                            ui.add(Label::new(RichText::new(format!("{:04X}", address)).color(color)).sense(Sense::click())),
+                            ui.add(Label::new(RichText::new(token.data).color(color)).sense(Sense::click())),
+                            ui.add(Label::new(RichText::new(token.opcode).color(color)).sense(Sense::click())),
+                            ui.add(Label::new(RichText::new(token.operands).color(color)).sense(Sense::click())),
+                        ];
+
+                        for cell in cells {
+                            let cell = if is_modified { cell.on_hover_text("Modified after being executed") } else { cell };
+                            if cell.double_clicked() {
+                                run_to = Some(address);
+                            }
+                            cell.context_menu(|ui| {
+                                if ui.button("Run to here").clicked() {
+                                    run_to = Some(address);
+                                    ui.close_menu();
+                                }
+                            });
+                        }
 
                         ui.end_row();
                         current_line += 1;
@@ -274,212 +372,217 @@ impl DisasmWidget {
                 });
             self.frame_data.previous_frame_editor_width = ui.min_rect().width();
         });
+
+        run_to
     }
 
     fn get_line_height(&self, ui: &mut Ui) -> f32 {
         ui.text_style_height(&self.options.text_style)
     }
 
-    fn initialize_known_addresses(&mut self) {
-        self.known_addrs[0xFC00] = Some("TMPADRL".to_string());
-        self.known_addrs[0xFC01] = Some("TMPADRH".to_string());
-        self.known_addrs[0xFC02] = Some("TILTACUML".to_string());
-        self.known_addrs[0xFC03] = Some("TILTACUMH".to_string());
-        self.known_addrs[0xFC04] = Some("HOFFL".to_string());
-        self.known_addrs[0xFC05] = Some("HOFFH".to_string());
-        self.known_addrs[0xFC06] = Some("VOFFL".to_string());
-        self.known_addrs[0xFC07] = Some("VOFFH".to_string());
-        self.known_addrs[0xFC08] = Some("VIDBASL".to_string());
-        self.known_addrs[0xFC09] = Some("VIDBASH".to_string());
-        self.known_addrs[0xFC0A] = Some("COLLBASL".to_string());
-        self.known_addrs[0xFC0B] = Some("COLLBASH".to_string());
-        self.known_addrs[0xFC0C] = Some("VIDADRL".to_string());
-        self.known_addrs[0xFC0D] = Some("VIDADRH".to_string());
-        self.known_addrs[0xFC0E] = Some("COLLADRL".to_string());
-        self.known_addrs[0xFC0F] = Some("COLLADRH".to_string());
-        self.known_addrs[0xFC10] = Some("SCBNEXTL".to_string());
-        self.known_addrs[0xFC11] = Some("SCBNEXTH".to_string());
-        self.known_addrs[0xFC12] = Some("SPRDLINEL".to_string());
-        self.known_addrs[0xFC13] = Some("SPRDLINEH".to_string());
-        self.known_addrs[0xFC14] = Some("HPOSSTRTL".to_string());
-        self.known_addrs[0xFC15] = Some("HPOSSTRTH".to_string());
-        self.known_addrs[0xFC16] = Some("VPOSSTRTL".to_string());
-        self.known_addrs[0xFC17] = Some("VPOSSTRTH".to_string());
-        self.known_addrs[0xFC18] = Some("SPRHSIZL".to_string());
-        self.known_addrs[0xFC19] = Some("SPRHSIZH".to_string());
-        self.known_addrs[0xFC1A] = Some("SPRVSIZL".to_string());
-        self.known_addrs[0xFC1B] = Some("SPRVSIZH".to_string());
-        self.known_addrs[0xFC1C] = Some("STRETCHL".to_string());
-        self.known_addrs[0xFC1D] = Some("STRETCHH".to_string());
-        self.known_addrs[0xFC1E] = Some("TILTL".to_string());
-        self.known_addrs[0xFC1F] = Some("TILTH".to_string());
-        self.known_addrs[0xFC20] = Some("SPRDOFFL".to_string());
-        self.known_addrs[0xFC21] = Some("SPRDOFFH".to_string());
-        self.known_addrs[0xFC22] = Some("SPRVPOSL".to_string());
-        self.known_addrs[0xFC23] = Some("SPRVPOSH".to_string());
-        self.known_addrs[0xFC24] = Some("COLLOFFL".to_string());
-        self.known_addrs[0xFC25] = Some("COLLOFFH".to_string());
-        self.known_addrs[0xFC26] = Some("VSIZACUML".to_string());
-        self.known_addrs[0xFC27] = Some("VSIZACUMH".to_string());
-        self.known_addrs[0xFC28] = Some("HSIZOFFL".to_string());
-        self.known_addrs[0xFC29] = Some("HSIZOFFH".to_string());
-        self.known_addrs[0xFC2A] = Some("VSIZOFFL".to_string());
-        self.known_addrs[0xFC2B] = Some("VSIZOFFH".to_string());
-        self.known_addrs[0xFC2C] = Some("SCBADRL".to_string());
-        self.known_addrs[0xFC2D] = Some("SCBADRH".to_string());
-        self.known_addrs[0xFC2E] = Some("PROCADRL".to_string());
-        self.known_addrs[0xFC2F] = Some("PROCADRH".to_string());
-        self.known_addrs[0xFC52] = Some("MATHD".to_string());
-        self.known_addrs[0xFC53] = Some("MATHC".to_string());
-        self.known_addrs[0xFC54] = Some("MATHB".to_string());
-        self.known_addrs[0xFC55] = Some("MATHA".to_string());
-        self.known_addrs[0xFC56] = Some("MATHP".to_string());
-        self.known_addrs[0xFC57] = Some("MATHN".to_string());
-        self.known_addrs[0xFC60] = Some("MATHH".to_string());
-        self.known_addrs[0xFC61] = Some("MATHG".to_string());
-        self.known_addrs[0xFC62] = Some("MATHF".to_string());
-        self.known_addrs[0xFC63] = Some("MATHE".to_string());
-        self.known_addrs[0xFC6C] = Some("MATHM".to_string());
-        self.known_addrs[0xFC6D] = Some("MATHL".to_string());
-        self.known_addrs[0xFC6E] = Some("MATHK".to_string());
-        self.known_addrs[0xFC6F] = Some("MATHJ".to_string());
-        self.known_addrs[0xFC80] = Some("SPRCTL0".to_string());
-        self.known_addrs[0xFC81] = Some("SPRCTL1".to_string());
-        self.known_addrs[0xFC82] = Some("SPRCOLL".to_string());
-        self.known_addrs[0xFC83] = Some("SPRINIT".to_string());
-        self.known_addrs[0xFC88] = Some("SUZYHREV".to_string());
-        self.known_addrs[0xFC89] = Some("SUZYSREV".to_string());
-        self.known_addrs[0xFC90] = Some("SUZYBUSEN".to_string());
-        self.known_addrs[0xFC91] = Some("SPRGO".to_string());
-        self.known_addrs[0xFC92] = Some("SPRSYS".to_string());
-        self.known_addrs[0xFCB0] = Some("JOYSTICK".to_string());
-        self.known_addrs[0xFCB1] = Some("SWITCHES".to_string());
-        self.known_addrs[0xFCB2] = Some("RCART0".to_string());
-        self.known_addrs[0xFCB3] = Some("RCART1".to_string());
-        self.known_addrs[0xFCC0] = Some("LEDS".to_string());
-        self.known_addrs[0xFCC2] = Some("PARSTATUS".to_string());
-        self.known_addrs[0xFCC3] = Some("PARDATA".to_string());
-        self.known_addrs[0xFCC4] = Some("HOWIE".to_string());
-        self.known_addrs[0xFD00] = Some("TIMER0".to_string());
-        self.known_addrs[0xFD04] = Some("TIMER1".to_string());
-        self.known_addrs[0xFD08] = Some("TIMER2".to_string());
-        self.known_addrs[0xFD0C] = Some("TIMER3".to_string());
-        self.known_addrs[0xFD10] = Some("TIMER4".to_string());
-        self.known_addrs[0xFD14] = Some("TIMER5".to_string());
-        self.known_addrs[0xFD18] = Some("TIMER6".to_string());
-        self.known_addrs[0xFD1C] = Some("TIMER7".to_string());
-        self.known_addrs[0xFD00] = Some("HTIMER".to_string());
-        self.known_addrs[0xFD08] = Some("VTIMER".to_string());
-        self.known_addrs[0xFD00] = Some("HTIMBKUP".to_string());
-        self.known_addrs[0xFD01] = Some("HTIMCTLA".to_string());
-        self.known_addrs[0xFD02] = Some("HTIMCNT".to_string());
-        self.known_addrs[0xFD03] = Some("HTIMCTLB".to_string());
-        self.known_addrs[0xFD08] = Some("VTIMBKUP".to_string());
-        self.known_addrs[0xFD09] = Some("VTIMCTLA".to_string());
-        self.known_addrs[0xFD0A] = Some("VTIMCNT".to_string());
-        self.known_addrs[0xFD0B] = Some("VTIMCTLB".to_string());
-        self.known_addrs[0xFD10] = Some("BAUDBKUP".to_string());
-        self.known_addrs[0xFD00] = Some("TIM0BKUP".to_string());
-        self.known_addrs[0xFD01] = Some("TIM0CTLA".to_string());
-        self.known_addrs[0xFD02] = Some("TIM0CNT".to_string());
-        self.known_addrs[0xFD03] = Some("TIM0CTLB".to_string());
-        self.known_addrs[0xFD04] = Some("TIM1BKUP".to_string());
-        self.known_addrs[0xFD05] = Some("TIM1CTLA".to_string());
-        self.known_addrs[0xFD06] = Some("TIM1CNT".to_string());
-        self.known_addrs[0xFD07] = Some("TIM1CTLB".to_string());
-        self.known_addrs[0xFD08] = Some("TIM2BKUP".to_string());
-        self.known_addrs[0xFD09] = Some("TIM2CTLA".to_string());
-        self.known_addrs[0xFD0A] = Some("TIM2CNT".to_string());
-        self.known_addrs[0xFD0B] = Some("TIM2CTLB".to_string());
-        self.known_addrs[0xFD0C] = Some("TIM3BKUP".to_string());
-        self.known_addrs[0xFD0D] = Some("TIM3CTLA".to_string());
-        self.known_addrs[0xFD0E] = Some("TIM3CNT".to_string());
-        self.known_addrs[0xFD0F] = Some("TIM3CTLB".to_string());
-        self.known_addrs[0xFD10] = Some("TIM4BKUP".to_string());
-        self.known_addrs[0xFD11] = Some("TIM4CTLA".to_string());
-        self.known_addrs[0xFD12] = Some("TIM4CNT".to_string());
-        self.known_addrs[0xFD13] = Some("TIM4CTLB".to_string());
-        self.known_addrs[0xFD14] = Some("TIM5BKUP".to_string());
-        self.known_addrs[0xFD15] = Some("TIM5CTLA".to_string());
-        self.known_addrs[0xFD16] = Some("TIM5CNT".to_string());
-        self.known_addrs[0xFD17] = Some("TIM5CTLB".to_string());
-        self.known_addrs[0xFD18] = Some("TIM6BKUP".to_string());
-        self.known_addrs[0xFD19] = Some("TIM6CTLA".to_string());
-        self.known_addrs[0xFD1A] = Some("TIM6CNT".to_string());
-        self.known_addrs[0xFD1B] = Some("TIM6CTLB".to_string());
-        self.known_addrs[0xFD1C] = Some("TIM7BKUP".to_string());
-        self.known_addrs[0xFD1D] = Some("TIM7CTLA".to_string());
-        self.known_addrs[0xFD1E] = Some("TIM7CNT".to_string());
-        self.known_addrs[0xFD1F] = Some("TIM7CTLB".to_string());
-        self.known_addrs[0xFD20] = Some("AUDIO0".to_string());
-        self.known_addrs[0xFD28] = Some("AUDIO1".to_string());
-        self.known_addrs[0xFD30] = Some("AUDIO2".to_string());
-        self.known_addrs[0xFD38] = Some("AUDIO3".to_string());
-        self.known_addrs[0xFD20] = Some("AUD0VOL".to_string());
-        self.known_addrs[0xFD21] = Some("AUD0FEED".to_string());
-        self.known_addrs[0xFD22] = Some("AUD0OUT".to_string());
-        self.known_addrs[0xFD23] = Some("AUD0SHIFT".to_string());
-        self.known_addrs[0xFD24] = Some("AUD0BKUP".to_string());
-        self.known_addrs[0xFD25] = Some("AUD0CTLA".to_string());
-        self.known_addrs[0xFD26] = Some("AUD0CNT".to_string());
-        self.known_addrs[0xFD27] = Some("AUD0CTLB".to_string());
-        self.known_addrs[0xFD28] = Some("AUD1VOL".to_string());
-        self.known_addrs[0xFD29] = Some("AUD1FEED".to_string());
-        self.known_addrs[0xFD2A] = Some("AUD1OUT".to_string());
-        self.known_addrs[0xFD2B] = Some("AUD1SHIFT".to_string());
-        self.known_addrs[0xFD2C] = Some("AUD1BKUP".to_string());
-        self.known_addrs[0xFD2D] = Some("AUD1CTLA".to_string());
-        self.known_addrs[0xFD2E] = Some("AUD1CNT".to_string());
-        self.known_addrs[0xFD2F] = Some("AUD1CTLB".to_string());
-        self.known_addrs[0xFD30] = Some("AUD2VOL".to_string());
-        self.known_addrs[0xFD31] = Some("AUD2FEED".to_string());
-        self.known_addrs[0xFD32] = Some("AUD2OUT".to_string());
-        self.known_addrs[0xFD33] = Some("AUD2SHIFT".to_string());
-        self.known_addrs[0xFD34] = Some("AUD2BKUP".to_string());
-        self.known_addrs[0xFD35] = Some("AUD2CTLA".to_string());
-        self.known_addrs[0xFD36] = Some("AUD2CNT".to_string());
-        self.known_addrs[0xFD37] = Some("AUD2CTLB".to_string());
-        self.known_addrs[0xFD38] = Some("AUD3VOL".to_string());
-        self.known_addrs[0xFD39] = Some("AUD3FEED".to_string());
-        self.known_addrs[0xFD3A] = Some("AUD3OUT".to_string());
-        self.known_addrs[0xFD3B] = Some("AUD3SHIFT".to_string());
-        self.known_addrs[0xFD3C] = Some("AUD3BKUP".to_string());
-        self.known_addrs[0xFD3D] = Some("AUD3CTLA".to_string());
-        self.known_addrs[0xFD3E] = Some("AUD3CNT".to_string());
-        self.known_addrs[0xFD3F] = Some("AUD3CTLB".to_string());
-        self.known_addrs[0xFD50] = Some("MSTEREO".to_string());
-        self.known_addrs[0xFD80] = Some("INTRST".to_string());
-        self.known_addrs[0xFD81] = Some("INTSET".to_string());
-        self.known_addrs[0xFD84] = Some("MAGRDY0".to_string());
-        self.known_addrs[0xFD85] = Some("MAGRDY1".to_string());
-        self.known_addrs[0xFD86] = Some("AUDIN".to_string());
-        self.known_addrs[0xFD87] = Some("SYSCTL1".to_string());
-        self.known_addrs[0xFD88] = Some("MIKEYHREV".to_string());
-        self.known_addrs[0xFD89] = Some("MIKEYSREV".to_string());
-        self.known_addrs[0xFD8A] = Some("IODIR".to_string());
-        self.known_addrs[0xFD8B] = Some("IODAT".to_string());
-        self.known_addrs[0xFD8C] = Some("SERCTL".to_string());
-        self.known_addrs[0xFD8D] = Some("SERDAT".to_string());
-        self.known_addrs[0xFD90] = Some("SDONEACK".to_string());
-        self.known_addrs[0xFD91] = Some("CPUSLEEP".to_string());
-        self.known_addrs[0xFD92] = Some("DISPCTL".to_string());
-        self.known_addrs[0xFD93] = Some("PBKUP".to_string());
-        self.known_addrs[0xFD94] = Some("DISPADRL".to_string());
-        self.known_addrs[0xFD95] = Some("DISPADRH".to_string());
-        self.known_addrs[0xFD9C] = Some("MTEST0".to_string());
-        self.known_addrs[0xFD9D] = Some("MTEST1".to_string());
-        self.known_addrs[0xFD9E] = Some("MTEST2".to_string());
-        self.known_addrs[0xFDA0] = Some("PALETTE".to_string());
-        self.known_addrs[0xFDA0] = Some("GCOLMAP".to_string());
-        self.known_addrs[0xFDB0] = Some("RBCOLMAP".to_string());
-        self.known_addrs[0xFFF9] = Some("MAPCTL".to_string());
-        self.known_addrs[0xFFFB] = Some("VECTORS".to_string());
-        self.known_addrs[0xFFFE] = Some("INTVECTL".to_string());
-        self.known_addrs[0xFFFF] = Some("INTVECTH".to_string());
-        self.known_addrs[0xFFFC] = Some("RSTVECTL".to_string());
-        self.known_addrs[0xFFFD] = Some("RSTVECTH".to_string());
-        self.known_addrs[0xFFFA] = Some("NMIVECTL".to_string());
-        self.known_addrs[0xFFFB] = Some("NMIVECTH".to_string());
+    fn known_addresses() -> Vec<Option<String>> {
+        const NONE: Option<String> = None;
+        let mut known_addrs = vec![NONE; 0xffff+1];
+        known_addrs[0xFC00] = Some("TMPADRL".to_string());
+        known_addrs[0xFC01] = Some("TMPADRH".to_string());
+        known_addrs[0xFC02] = Some("TILTACUML".to_string());
+        known_addrs[0xFC03] = Some("TILTACUMH".to_string());
+        known_addrs[0xFC04] = Some("HOFFL".to_string());
+        known_addrs[0xFC05] = Some("HOFFH".to_string());
+        known_addrs[0xFC06] = Some("VOFFL".to_string());
+        known_addrs[0xFC07] = Some("VOFFH".to_string());
+        known_addrs[0xFC08] = Some("VIDBASL".to_string());
+        known_addrs[0xFC09] = Some("VIDBASH".to_string());
+        known_addrs[0xFC0A] = Some("COLLBASL".to_string());
+        known_addrs[0xFC0B] = Some("COLLBASH".to_string());
+        known_addrs[0xFC0C] = Some("VIDADRL".to_string());
+        known_addrs[0xFC0D] = Some("VIDADRH".to_string());
+        known_addrs[0xFC0E] = Some("COLLADRL".to_string());
+        known_addrs[0xFC0F] = Some("COLLADRH".to_string());
+        known_addrs[0xFC10] = Some("SCBNEXTL".to_string());
+        known_addrs[0xFC11] = Some("SCBNEXTH".to_string());
+        known_addrs[0xFC12] = Some("SPRDLINEL".to_string());
+        known_addrs[0xFC13] = Some("SPRDLINEH".to_string());
+        known_addrs[0xFC14] = Some("HPOSSTRTL".to_string());
+        known_addrs[0xFC15] = Some("HPOSSTRTH".to_string());
+        known_addrs[0xFC16] = Some("VPOSSTRTL".to_string());
+        known_addrs[0xFC17] = Some("VPOSSTRTH".to_string());
+        known_addrs[0xFC18] = Some("SPRHSIZL".to_string());
+        known_addrs[0xFC19] = Some("SPRHSIZH".to_string());
+        known_addrs[0xFC1A] = Some("SPRVSIZL".to_string());
+        known_addrs[0xFC1B] = Some("SPRVSIZH".to_string());
+        known_addrs[0xFC1C] = Some("STRETCHL".to_string());
+        known_addrs[0xFC1D] = Some("STRETCHH".to_string());
+        known_addrs[0xFC1E] = Some("TILTL".to_string());
+        known_addrs[0xFC1F] = Some("TILTH".to_string());
+        known_addrs[0xFC20] = Some("SPRDOFFL".to_string());
+        known_addrs[0xFC21] = Some("SPRDOFFH".to_string());
+        known_addrs[0xFC22] = Some("SPRVPOSL".to_string());
+        known_addrs[0xFC23] = Some("SPRVPOSH".to_string());
+        known_addrs[0xFC24] = Some("COLLOFFL".to_string());
+        known_addrs[0xFC25] = Some("COLLOFFH".to_string());
+        known_addrs[0xFC26] = Some("VSIZACUML".to_string());
+        known_addrs[0xFC27] = Some("VSIZACUMH".to_string());
+        known_addrs[0xFC28] = Some("HSIZOFFL".to_string());
+        known_addrs[0xFC29] = Some("HSIZOFFH".to_string());
+        known_addrs[0xFC2A] = Some("VSIZOFFL".to_string());
+        known_addrs[0xFC2B] = Some("VSIZOFFH".to_string());
+        known_addrs[0xFC2C] = Some("SCBADRL".to_string());
+        known_addrs[0xFC2D] = Some("SCBADRH".to_string());
+        known_addrs[0xFC2E] = Some("PROCADRL".to_string());
+        known_addrs[0xFC2F] = Some("PROCADRH".to_string());
+        known_addrs[0xFC52] = Some("MATHD".to_string());
+        known_addrs[0xFC53] = Some("MATHC".to_string());
+        known_addrs[0xFC54] = Some("MATHB".to_string());
+        known_addrs[0xFC55] = Some("MATHA".to_string());
+        known_addrs[0xFC56] = Some("MATHP".to_string());
+        known_addrs[0xFC57] = Some("MATHN".to_string());
+        known_addrs[0xFC60] = Some("MATHH".to_string());
+        known_addrs[0xFC61] = Some("MATHG".to_string());
+        known_addrs[0xFC62] = Some("MATHF".to_string());
+        known_addrs[0xFC63] = Some("MATHE".to_string());
+        known_addrs[0xFC6C] = Some("MATHM".to_string());
+        known_addrs[0xFC6D] = Some("MATHL".to_string());
+        known_addrs[0xFC6E] = Some("MATHK".to_string());
+        known_addrs[0xFC6F] = Some("MATHJ".to_string());
+        known_addrs[0xFC80] = Some("SPRCTL0".to_string());
+        known_addrs[0xFC81] = Some("SPRCTL1".to_string());
+        known_addrs[0xFC82] = Some("SPRCOLL".to_string());
+        known_addrs[0xFC83] = Some("SPRINIT".to_string());
+        known_addrs[0xFC88] = Some("SUZYHREV".to_string());
+        known_addrs[0xFC89] = Some("SUZYSREV".to_string());
+        known_addrs[0xFC90] = Some("SUZYBUSEN".to_string());
+        known_addrs[0xFC91] = Some("SPRGO".to_string());
+        known_addrs[0xFC92] = Some("SPRSYS".to_string());
+        known_addrs[0xFCB0] = Some("JOYSTICK".to_string());
+        known_addrs[0xFCB1] = Some("SWITCHES".to_string());
+        known_addrs[0xFCB2] = Some("RCART0".to_string());
+        known_addrs[0xFCB3] = Some("RCART1".to_string());
+        known_addrs[0xFCC0] = Some("LEDS".to_string());
+        known_addrs[0xFCC2] = Some("PARSTATUS".to_string());
+        known_addrs[0xFCC3] = Some("PARDATA".to_string());
+        known_addrs[0xFCC4] = Some("HOWIE".to_string());
+        known_addrs[0xFD00] = Some("TIMER0".to_string());
+        known_addrs[0xFD04] = Some("TIMER1".to_string());
+        known_addrs[0xFD08] = Some("TIMER2".to_string());
+        known_addrs[0xFD0C] = Some("TIMER3".to_string());
+        known_addrs[0xFD10] = Some("TIMER4".to_string());
+        known_addrs[0xFD14] = Some("TIMER5".to_string());
+        known_addrs[0xFD18] = Some("TIMER6".to_string());
+        known_addrs[0xFD1C] = Some("TIMER7".to_string());
+        known_addrs[0xFD00] = Some("HTIMER".to_string());
+        known_addrs[0xFD08] = Some("VTIMER".to_string());
+        known_addrs[0xFD00] = Some("HTIMBKUP".to_string());
+        known_addrs[0xFD01] = Some("HTIMCTLA".to_string());
+        known_addrs[0xFD02] = Some("HTIMCNT".to_string());
+        known_addrs[0xFD03] = Some("HTIMCTLB".to_string());
+        known_addrs[0xFD08] = Some("VTIMBKUP".to_string());
+        known_addrs[0xFD09] = Some("VTIMCTLA".to_string());
+        known_addrs[0xFD0A] = Some("VTIMCNT".to_string());
+        known_addrs[0xFD0B] = Some("VTIMCTLB".to_string());
+        known_addrs[0xFD10] = Some("BAUDBKUP".to_string());
+        known_addrs[0xFD00] = Some("TIM0BKUP".to_string());
+        known_addrs[0xFD01] = Some("TIM0CTLA".to_string());
+        known_addrs[0xFD02] = Some("TIM0CNT".to_string());
+        known_addrs[0xFD03] = Some("TIM0CTLB".to_string());
+        known_addrs[0xFD04] = Some("TIM1BKUP".to_string());
+        known_addrs[0xFD05] = Some("TIM1CTLA".to_string());
+        known_addrs[0xFD06] = Some("TIM1CNT".to_string());
+        known_addrs[0xFD07] = Some("TIM1CTLB".to_string());
+        known_addrs[0xFD08] = Some("TIM2BKUP".to_string());
+        known_addrs[0xFD09] = Some("TIM2CTLA".to_string());
+        known_addrs[0xFD0A] = Some("TIM2CNT".to_string());
+        known_addrs[0xFD0B] = Some("TIM2CTLB".to_string());
+        known_addrs[0xFD0C] = Some("TIM3BKUP".to_string());
+        known_addrs[0xFD0D] = Some("TIM3CTLA".to_string());
+        known_addrs[0xFD0E] = Some("TIM3CNT".to_string());
+        known_addrs[0xFD0F] = Some("TIM3CTLB".to_string());
+        known_addrs[0xFD10] = Some("TIM4BKUP".to_string());
+        known_addrs[0xFD11] = Some("TIM4CTLA".to_string());
+        known_addrs[0xFD12] = Some("TIM4CNT".to_string());
+        known_addrs[0xFD13] = Some("TIM4CTLB".to_string());
+        known_addrs[0xFD14] = Some("TIM5BKUP".to_string());
+        known_addrs[0xFD15] = Some("TIM5CTLA".to_string());
+        known_addrs[0xFD16] = Some("TIM5CNT".to_string());
+        known_addrs[0xFD17] = Some("TIM5CTLB".to_string());
+        known_addrs[0xFD18] = Some("TIM6BKUP".to_string());
+        known_addrs[0xFD19] = Some("TIM6CTLA".to_string());
+        known_addrs[0xFD1A] = Some("TIM6CNT".to_string());
+        known_addrs[0xFD1B] = Some("TIM6CTLB".to_string());
+        known_addrs[0xFD1C] = Some("TIM7BKUP".to_string());
+        known_addrs[0xFD1D] = Some("TIM7CTLA".to_string());
+        known_addrs[0xFD1E] = Some("TIM7CNT".to_string());
+        known_addrs[0xFD1F] = Some("TIM7CTLB".to_string());
+        known_addrs[0xFD20] = Some("AUDIO0".to_string());
+        known_addrs[0xFD28] = Some("AUDIO1".to_string());
+        known_addrs[0xFD30] = Some("AUDIO2".to_string());
+        known_addrs[0xFD38] = Some("AUDIO3".to_string());
+        known_addrs[0xFD20] = Some("AUD0VOL".to_string());
+        known_addrs[0xFD21] = Some("AUD0FEED".to_string());
+        known_addrs[0xFD22] = Some("AUD0OUT".to_string());
+        known_addrs[0xFD23] = Some("AUD0SHIFT".to_string());
+        known_addrs[0xFD24] = Some("AUD0BKUP".to_string());
+        known_addrs[0xFD25] = Some("AUD0CTLA".to_string());
+        known_addrs[0xFD26] = Some("AUD0CNT".to_string());
+        known_addrs[0xFD27] = Some("AUD0CTLB".to_string());
+        known_addrs[0xFD28] = Some("AUD1VOL".to_string());
+        known_addrs[0xFD29] = Some("AUD1FEED".to_string());
+        known_addrs[0xFD2A] = Some("AUD1OUT".to_string());
+        known_addrs[0xFD2B] = Some("AUD1SHIFT".to_string());
+        known_addrs[0xFD2C] = Some("AUD1BKUP".to_string());
+        known_addrs[0xFD2D] = Some("AUD1CTLA".to_string());
+        known_addrs[0xFD2E] = Some("AUD1CNT".to_string());
+        known_addrs[0xFD2F] = Some("AUD1CTLB".to_string());
+        known_addrs[0xFD30] = Some("AUD2VOL".to_string());
+        known_addrs[0xFD31] = Some("AUD2FEED".to_string());
+        known_addrs[0xFD32] = Some("AUD2OUT".to_string());
+        known_addrs[0xFD33] = Some("AUD2SHIFT".to_string());
+        known_addrs[0xFD34] = Some("AUD2BKUP".to_string());
+        known_addrs[0xFD35] = Some("AUD2CTLA".to_string());
+        known_addrs[0xFD36] = Some("AUD2CNT".to_string());
+        known_addrs[0xFD37] = Some("AUD2CTLB".to_string());
+        known_addrs[0xFD38] = Some("AUD3VOL".to_string());
+        known_addrs[0xFD39] = Some("AUD3FEED".to_string());
+        known_addrs[0xFD3A] = Some("AUD3OUT".to_string());
+        known_addrs[0xFD3B] = Some("AUD3SHIFT".to_string());
+        known_addrs[0xFD3C] = Some("AUD3BKUP".to_string());
+        known_addrs[0xFD3D] = Some("AUD3CTLA".to_string());
+        known_addrs[0xFD3E] = Some("AUD3CNT".to_string());
+        known_addrs[0xFD3F] = Some("AUD3CTLB".to_string());
+        known_addrs[0xFD50] = Some("MSTEREO".to_string());
+        known_addrs[0xFD80] = Some("INTRST".to_string());
+        known_addrs[0xFD81] = Some("INTSET".to_string());
+        known_addrs[0xFD84] = Some("MAGRDY0".to_string());
+        known_addrs[0xFD85] = Some("MAGRDY1".to_string());
+        known_addrs[0xFD86] = Some("AUDIN".to_string());
+        known_addrs[0xFD87] = Some("SYSCTL1".to_string());
+        known_addrs[0xFD88] = Some("MIKEYHREV".to_string());
+        known_addrs[0xFD89] = Some("MIKEYSREV".to_string());
+        known_addrs[0xFD8A] = Some("IODIR".to_string());
+        known_addrs[0xFD8B] = Some("IODAT".to_string());
+        known_addrs[0xFD8C] = Some("SERCTL".to_string());
+        known_addrs[0xFD8D] = Some("SERDAT".to_string());
+        known_addrs[0xFD90] = Some("SDONEACK".to_string());
+        known_addrs[0xFD91] = Some("CPUSLEEP".to_string());
+        known_addrs[0xFD92] = Some("DISPCTL".to_string());
+        known_addrs[0xFD93] = Some("PBKUP".to_string());
+        known_addrs[0xFD94] = Some("DISPADRL".to_string());
+        known_addrs[0xFD95] = Some("DISPADRH".to_string());
+        known_addrs[0xFD9C] = Some("MTEST0".to_string());
+        known_addrs[0xFD9D] = Some("MTEST1".to_string());
+        known_addrs[0xFD9E] = Some("MTEST2".to_string());
+        known_addrs[0xFDA0] = Some("PALETTE".to_string());
+        known_addrs[0xFDA0] = Some("GCOLMAP".to_string());
+        known_addrs[0xFDB0] = Some("RBCOLMAP".to_string());
+        known_addrs[0xFFF9] = Some("MAPCTL".to_string());
+        known_addrs[0xFFFB] = Some("VECTORS".to_string());
+        known_addrs[0xFFFE] = Some("INTVECTL".to_string());
+        known_addrs[0xFFFF] = Some("INTVECTH".to_string());
+        known_addrs[0xFFFC] = Some("RSTVECTL".to_string());
+        known_addrs[0xFFFD] = Some("RSTVECTH".to_string());
+        known_addrs[0xFFFA] = Some("NMIVECTL".to_string());
+        known_addrs[0xFFFB] = Some("NMIVECTH".to_string());
+        known_addrs
     }
 }
 
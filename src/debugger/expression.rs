@@ -0,0 +1,547 @@
+use holani::lynx::Lynx;
+
+use super::{disassembler::hardware_register, labels::Label};
+
+/// Values an expression can read from the emulator.
+pub trait ExpressionContext {
+    fn register(&self, register: Register) -> u16;
+    fn memory(&self, addr: u16) -> u8;
+
+    /// Address of a user label.
+    fn symbol(&self, _name: &str) -> Option<u16> {
+        None
+    }
+}
+
+impl ExpressionContext for Lynx {
+    fn register(&self, register: Register) -> u16 {
+        let cpu = self.mikey().cpu();
+        match register {
+            Register::A => cpu.a() as u16,
+            Register::X => cpu.x() as u16,
+            Register::Y => cpu.y() as u16,
+            Register::S => cpu.s() as u16,
+            Register::P => cpu.flags().bits() as u16,
+            Register::PC => cpu.last_ir_pc,
+        }
+    }
+
+    fn memory(&self, addr: u16) -> u8 {
+        self.cpu_mem(addr)
+    }
+}
+
+/// Emulator state along with the user labels, so that expressions can name addresses.
+pub struct SymbolContext<'a> {
+    pub lynx: &'a Lynx,
+    pub labels: &'a [Label],
+}
+
+impl ExpressionContext for SymbolContext<'_> {
+    fn register(&self, register: Register) -> u16 {
+        ExpressionContext::register(self.lynx, register)
+    }
+
+    fn memory(&self, addr: u16) -> u8 {
+        ExpressionContext::memory(self.lynx, addr)
+    }
+
+    fn symbol(&self, name: &str) -> Option<u16> {
+        self.labels.iter().find(|l| l.name == name).map(|l| l.addr)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    S,
+    P,
+    PC,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Not,
+    Complement,
+    Negate,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// Parsed expression, e.g. `A == $10 && [$80] > 3 && X != Y`.
+///
+/// Numbers are decimal, `$` hexadecimal or `%` binary. `A`, `X`, `Y`, `S`, `P` and `PC`
+/// read the CPU registers, `N`, `V`, `B`, `D`, `I`, `Z` and `C` the flags, and `[addr]`
+/// the byte the CPU sees at `addr`. Operators follow C precedence, comparisons yield 0 or 1.
+///
+/// `word(addr)` reads a little-endian word, `[addr,index]` the byte at `addr + index`, and
+/// `[[ptr],Y]` goes through the zero page pointer at `ptr` like `(ptr),Y`. Other names are
+/// hardware registers or user labels, evaluating to their address, anything else is an error.
+#[derive(Clone)]
+pub enum Expr {
+    Number(i64),
+    Register(Register),
+    Flag(u8),
+    Symbol(String),
+    Memory(Box<Expr>),
+    Word(Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// `labels` are the names accepted besides the registers, flags and hardware registers.
+    pub fn parse(text: &str, labels: &[Label]) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0, labels };
+        let expr = parser.expression(0)?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(t) => Err(format!("unexpected '{}'", t)),
+        }
+    }
+
+    /// True when the value comes from memory, through `[addr]` or `word(addr)`.
+    pub fn reads_memory(&self) -> bool {
+        match self {
+            Expr::Memory(_) | Expr::Word(_) => true,
+            Expr::Number(_) | Expr::Register(_) | Expr::Flag(_) | Expr::Symbol(_) => false,
+            Expr::Unary(_, e) => e.reads_memory(),
+            Expr::Binary(_, l, r) => l.reads_memory() || r.reads_memory(),
+        }
+    }
+
+    /// Hexadecimal digits the value is shown with, 4 for addresses, words and `PC`, 2 for bytes.
+    pub fn hex_digits(&self) -> usize {
+        match self {
+            Expr::Number(v) => if (0..=0xFF).contains(v) { 2 } else { 4 },
+            Expr::Register(Register::PC) | Expr::Symbol(_) | Expr::Word(_) => 4,
+            Expr::Register(_) | Expr::Flag(_) | Expr::Memory(_) => 2,
+            Expr::Unary(_, e) => e.hex_digits(),
+            Expr::Binary(op, l, r) => match op {
+                BinaryOp::Or | BinaryOp::And | BinaryOp::Eq | BinaryOp::Ne
+                | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 2,
+                _ => l.hex_digits().max(r.hex_digits()),
+            },
+        }
+    }
+
+    pub fn eval(&self, ctx: &impl ExpressionContext) -> i64 {
+        match self {
+            Expr::Number(v) => *v,
+            Expr::Register(r) => ctx.register(*r) as i64,
+            Expr::Flag(mask) => (ctx.register(Register::P) as u8 & mask != 0) as i64,
+            Expr::Symbol(name) => ctx.symbol(name).map_or(0, |addr| addr as i64),
+            Expr::Memory(addr) => ctx.memory(addr.eval(ctx) as u16) as i64,
+            Expr::Word(addr) => {
+                let addr = addr.eval(ctx) as u16;
+                ctx.memory(addr) as i64 | (ctx.memory(addr.wrapping_add(1)) as i64) << 8
+            }
+            Expr::Unary(op, e) => {
+                let v = e.eval(ctx);
+                match op {
+                    UnaryOp::Not => (v == 0) as i64,
+                    UnaryOp::Complement => !v,
+                    UnaryOp::Negate => v.wrapping_neg(),
+                }
+            }
+            Expr::Binary(op, l, r) => {
+                let l = l.eval(ctx);
+                // Short-circuit so that memory on the right side is only read when needed.
+                match op {
+                    BinaryOp::Or => return (l != 0 || r.eval(ctx) != 0) as i64,
+                    BinaryOp::And => return (l != 0 && r.eval(ctx) != 0) as i64,
+                    _ => (),
+                }
+                let r = r.eval(ctx);
+                match op {
+                    BinaryOp::Or | BinaryOp::And => unreachable!(),
+                    BinaryOp::BitOr => l | r,
+                    BinaryOp::BitXor => l ^ r,
+                    BinaryOp::BitAnd => l & r,
+                    BinaryOp::Eq => (l == r) as i64,
+                    BinaryOp::Ne => (l != r) as i64,
+                    BinaryOp::Lt => (l < r) as i64,
+                    BinaryOp::Le => (l <= r) as i64,
+                    BinaryOp::Gt => (l > r) as i64,
+                    BinaryOp::Ge => (l >= r) as i64,
+                    BinaryOp::Shl => l.wrapping_shl(r as u32),
+                    BinaryOp::Shr => l.wrapping_shr(r as u32),
+                    BinaryOp::Add => l.wrapping_add(r),
+                    BinaryOp::Sub => l.wrapping_sub(r),
+                    BinaryOp::Mul => l.wrapping_mul(r),
+                    BinaryOp::Div => l.checked_div(r).unwrap_or(0),
+                    BinaryOp::Rem => l.checked_rem(r).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(v) => write!(f, "{}", v),
+            Token::Ident(s) => write!(f, "{}", s),
+            Token::Op(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+const OPERATORS: [&str; 25] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>",
+    "|", "^", "&", "<", ">", "+", "-", "*", "/", "%", "!", "~", "(", ")", "[", "]", ",",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = text.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        // `%` after a value is the remainder operator.
+        let after_value = matches!(tokens.last(), Some(Token::Number(_) | Token::Ident(_) | Token::Op(")") | Token::Op("]")));
+        let radix = match c {
+            '$' => Some(16),
+            '%' if !after_value => Some(2),
+            _ => None,
+        };
+
+        if let Some(radix) = radix {
+            let digits: String = rest[1..].chars().take_while(|c| c.is_digit(radix)).collect();
+            match i64::from_str_radix(&digits, radix) {
+                Err(_) => return Err(format!("invalid number '{}'", &rest[..=digits.len()])),
+                Ok(v) => tokens.push(Token::Number(v)),
+            }
+            rest = &rest[1 + digits.len()..];
+        } else if c.is_ascii_digit() {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            match digits.parse::<i64>() {
+                Err(_) => return Err(format!("invalid number '{}'", digits)),
+                Ok(v) => tokens.push(Token::Number(v)),
+            }
+            rest = &rest[digits.len()..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let ident: String = rest.chars().take_while(|c| c.is_ascii_alphanumeric() || *c == '_').collect();
+            rest = &rest[ident.len()..];
+            tokens.push(Token::Ident(ident));
+        } else {
+            match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                None => return Err(format!("unexpected '{}'", c)),
+                Some(op) => {
+                    tokens.push(Token::Op(op));
+                    rest = &rest[op.len()..];
+                }
+            }
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+fn binary_op(op: &str) -> Option<(BinaryOp, u8)> {
+    Some(match op {
+        "||" => (BinaryOp::Or, 1),
+        "&&" => (BinaryOp::And, 2),
+        "|" => (BinaryOp::BitOr, 3),
+        "^" => (BinaryOp::BitXor, 4),
+        "&" => (BinaryOp::BitAnd, 5),
+        "==" => (BinaryOp::Eq, 6),
+        "!=" => (BinaryOp::Ne, 6),
+        "<" => (BinaryOp::Lt, 7),
+        "<=" => (BinaryOp::Le, 7),
+        ">" => (BinaryOp::Gt, 7),
+        ">=" => (BinaryOp::Ge, 7),
+        "<<" => (BinaryOp::Shl, 8),
+        ">>" => (BinaryOp::Shr, 8),
+        "+" => (BinaryOp::Add, 9),
+        "-" => (BinaryOp::Sub, 9),
+        "*" => (BinaryOp::Mul, 10),
+        "/" => (BinaryOp::Div, 10),
+        "%" => (BinaryOp::Rem, 10),
+        _ => return None,
+    })
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    labels: &'a [Label],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Op(o)) if o == op => Ok(()),
+            Some(t) => Err(format!("expected '{}', found '{}'", op, t)),
+            None => Err(format!("expected '{}'", op)),
+        }
+    }
+
+    /// Precedence climbing, only binds operators stronger than `min`.
+    fn expression(&mut self, min: u8) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let (op, prec) = match binary_op(op) {
+                Some((op, prec)) if prec > min => (op, prec),
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.expression(prec)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let op = match self.peek() {
+            Some(Token::Op("!")) => UnaryOp::Not,
+            Some(Token::Op("~")) => UnaryOp::Complement,
+            Some(Token::Op("-")) => UnaryOp::Negate,
+            _ => return self.primary(),
+        };
+        self.pos += 1;
+        Ok(Expr::Unary(op, Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            None => Err("unexpected end of expression".to_string()),
+            Some(Token::Number(v)) => Ok(Expr::Number(v)),
+            Some(Token::Op("(")) => {
+                let e = self.expression(0)?;
+                self.expect(")")?;
+                Ok(e)
+            }
+            Some(Token::Op("[")) => {
+                let mut e = self.expression(0)?;
+                if self.peek() == Some(&Token::Op(",")) {
+                    self.pos += 1;
+                    let index = self.expression(0)?;
+                    // `[[ptr],Y]` follows the pointer, as the 6502 does.
+                    if let Expr::Memory(ptr) = e {
+                        e = Expr::Word(ptr);
+                    }
+                    e = Expr::Binary(BinaryOp::Add, Box::new(e), Box::new(index));
+                }
+                self.expect("]")?;
+                Ok(Expr::Memory(Box::new(e)))
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::Op("(")) => {
+                self.pos += 1;
+                let e = self.expression(0)?;
+                self.expect(")")?;
+                match name.to_ascii_lowercase().as_str() {
+                    "word" => Ok(Expr::Word(Box::new(e))),
+                    _ => Err(format!("unknown function '{}'", name)),
+                }
+            }
+            Some(Token::Ident(name)) => identifier(&name, self.labels),
+            Some(t) => Err(format!("unexpected '{}'", t)),
+        }
+    }
+}
+
+/// Labels are resolved when evaluating, so that moving one doesn't need a new parse.
+fn identifier(name: &str, labels: &[Label]) -> Result<Expr, String> {
+    Ok(match name.to_ascii_uppercase().as_str() {
+        "A" => Expr::Register(Register::A),
+        "X" => Expr::Register(Register::X),
+        "Y" => Expr::Register(Register::Y),
+        "S" => Expr::Register(Register::S),
+        "P" => Expr::Register(Register::P),
+        "PC" => Expr::Register(Register::PC),
+        "N" => Expr::Flag(0x80),
+        "V" => Expr::Flag(0x40),
+        "B" => Expr::Flag(0x10),
+        "D" => Expr::Flag(0x08),
+        "I" => Expr::Flag(0x04),
+        "Z" => Expr::Flag(0x02),
+        "C" => Expr::Flag(0x01),
+        upper => match hardware_register(upper) {
+            Some(addr) => Expr::Number(addr as i64),
+            None if labels.iter().any(|l| l.name == name) => Expr::Symbol(name.to_string()),
+            None => return Err(format!("unknown name '{}'", name)),
+        },
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Context shared by the tests of the modules evaluating expressions, memory starts zeroed.
+    pub(crate) struct TestContext {
+        pub(crate) memory: Vec<u8>,
+        pub(crate) p: u8,
+    }
+
+    impl TestContext {
+        pub(crate) fn new() -> Self {
+            Self { memory: vec![0; 0x10000], p: 0 }
+        }
+    }
+
+    impl ExpressionContext for TestContext {
+        fn register(&self, register: Register) -> u16 {
+            match register {
+                Register::A => 0x12,
+                Register::X => 0x34,
+                Register::Y => 0x02,
+                Register::S => 0xFD,
+                Register::P => self.p as u16,
+                Register::PC => 0x0250,
+            }
+        }
+
+        fn memory(&self, addr: u16) -> u8 {
+            self.memory[addr as usize]
+        }
+
+        fn symbol(&self, name: &str) -> Option<u16> {
+            (name == "player_x").then_some(0x0300)
+        }
+    }
+
+    fn labels() -> Vec<Label> {
+        vec![Label { name: "player_x".to_string(), addr: 0x0300 }]
+    }
+
+    fn eval(text: &str, ctx: &TestContext) -> i64 {
+        Expr::parse(text, &labels()).unwrap_or_else(|e| panic!("{}: {}", text, e)).eval(ctx)
+    }
+
+    #[test]
+    fn numbers() {
+        let ctx = TestContext::new();
+        assert_eq!(eval("42", &ctx), 42);
+        assert_eq!(eval("$fF", &ctx), 0xFF);
+        assert_eq!(eval("%101", &ctx), 5);
+        assert_eq!(eval("7 % 4", &ctx), 3);
+    }
+
+    #[test]
+    fn operators() {
+        let ctx = TestContext::new();
+        assert_eq!(eval("1 + 2 * 3", &ctx), 7);
+        assert_eq!(eval("(1 + 2) * 3", &ctx), 9);
+        assert_eq!(eval("1 | 2 == 2", &ctx), 1);
+        assert_eq!(eval("1 << 4 >> 2", &ctx), 4);
+        assert_eq!(eval("-1", &ctx), -1);
+        assert_eq!(eval("~0", &ctx), -1);
+        assert_eq!(eval("!5", &ctx), 0);
+        assert_eq!(eval("3 > 2 && 2 >= 2 && 1 != 2", &ctx), 1);
+        assert_eq!(eval("0 || 2 < 1", &ctx), 0);
+        assert_eq!(eval("1 / 0", &ctx), 0);
+        assert_eq!(eval("1 % 0", &ctx), 0);
+    }
+
+    #[test]
+    fn registers_and_flags() {
+        let mut ctx = TestContext::new();
+        ctx.p = 0x81;
+        assert_eq!(eval("A == $12 && x == $34", &ctx), 1);
+        assert_eq!(eval("PC", &ctx), 0x0250);
+        assert_eq!(eval("N", &ctx), 1);
+        assert_eq!(eval("C", &ctx), 1);
+        assert_eq!(eval("Z", &ctx), 0);
+        assert_eq!(eval("P", &ctx), 0x81);
+    }
+
+    #[test]
+    fn memory() {
+        let mut ctx = TestContext::new();
+        ctx.memory[0x80] = 0x05;
+        ctx.memory[0x81] = 0x30;
+        ctx.memory[0xB4] = 0x09;
+        assert_eq!(eval("[$80]", &ctx), 0x05);
+        assert_eq!(eval("[$80] + [$81]", &ctx), 0x35);
+        assert_eq!(eval("[$80,X]", &ctx), 0x09);
+        assert_eq!(eval("word($80)", &ctx), 0x3005);
+    }
+
+    #[test]
+    fn reads_memory() {
+        let parse = |text| Expr::parse(text, &labels()).unwrap();
+        assert!(parse("[$80]").reads_memory());
+        assert!(parse("player_x + WORD($80)").reads_memory());
+        assert!(parse("-[[$80],Y]").reads_memory());
+        assert!(!parse("player_x + 2").reads_memory());
+        assert!(!parse("$0300").reads_memory());
+    }
+
+    #[test]
+    fn indirect_indexed() {
+        let mut ctx = TestContext::new();
+        ctx.memory[0x80] = 0x00;
+        ctx.memory[0x81] = 0x30;
+        ctx.memory[0x3002] = 0x77;
+        assert_eq!(eval("[[$80],Y]", &ctx), 0x77);
+        assert_eq!(eval("[[$80],1]", &ctx), 0x00);
+        assert_eq!(eval("[ [ $7F + 1 ] , Y + 1 ]", &ctx), 0x00);
+        assert!(Expr::parse("[[$80],Y", &[]).is_err());
+        assert!(Expr::parse("[[$80]Y]", &[]).is_err());
+    }
+
+    #[test]
+    fn names() {
+        let ctx = TestContext::new();
+        assert_eq!(eval("player_x", &ctx), 0x0300);
+        assert_eq!(eval("INTSET", &ctx), 0xFD81);
+        assert_eq!(eval("intset", &ctx), 0xFD81);
+    }
+
+    #[test]
+    fn unknown_names() {
+        assert_eq!(Expr::parse("plyer_x == 3", &labels()).err().as_deref(), Some("unknown name 'plyer_x'"));
+        assert!(Expr::parse("player_x", &[]).is_err());
+        assert!(Expr::parse("Player_X", &labels()).is_err());
+        assert!(Expr::parse("[INTSETT]", &labels()).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        for text in ["", "1 +", "(1", "[1", "1 2", "$", "foo(1)", "A # 1"] {
+            assert!(Expr::parse(text, &labels()).is_err(), "{}", text);
+        }
+    }
+}
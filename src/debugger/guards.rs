@@ -0,0 +1,335 @@
+use std::sync::Arc;
+
+use egui::{mutex::RwLock, DragValue, RichText, Widget};
+use holani::lynx::Lynx;
+
+use super::{disassembler::{is_illegal, next_address, Access, MemoryAccess}, hex_input, log_pane::LogPane};
+
+/// Largest S change a single instruction or interrupt can make.
+const MAX_STACK_STEP: u8 = 3;
+
+/// `TXS` reloads S, whatever the jump.
+const TXS: u8 = 0x9A;
+
+/// Start of the hardware registers and boot ROM, never tracked as RAM.
+const HARDWARE_START: u16 = 0xFC00;
+
+/// Suzy registers giving the video and collision buffers and the SCB collision depository offset.
+const VIDBAS: u16 = 0xFC08;
+const COLLBAS: u16 = 0xFC0A;
+const COLLOFF: u16 = 0xFC24;
+/// 160x102 pixels at 4 bits each.
+const BUFFER_SIZE: u16 = 160 * 102 / 2;
+
+/// One bit per address of the 64K address space.
+pub struct AddressSet {
+    bits: Vec<u64>,
+}
+
+impl AddressSet {
+    pub fn new() -> Self {
+        Self {
+            bits: vec![0; 0x10000 / 64],
+        }
+    }
+
+    pub fn insert(&mut self, addr: u16) {
+        self.bits[addr as usize / 64] |= 1 << (addr % 64);
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        self.bits[addr as usize / 64] & (1 << (addr % 64)) != 0
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+fn suzy_word(lynx: &Lynx, addr: u16) -> u16 {
+    let regs = lynx.suzy().registers();
+    (regs.data(addr) as u16) | ((regs.data(addr + 1) as u16) << 8)
+}
+
+/// Monitors checked before each instruction, catching bugs that don't stop at a known address.
+pub struct Guards {
+    last_s: u8,
+    /// Opcode of the last instruction tracked, the one that made the current S.
+    last_opcode: u8,
+    stack_lowest: u8,
+    break_on_stack_wrap: bool,
+    break_on_stack_threshold: bool,
+    stack_threshold: u8,
+    /// RAM written since reset, seeded with the bytes the loader wrote.
+    written: AddressSet,
+    data_regions: Vec<(u16, u16)>,
+    data_start: u16,
+    data_start_buffer: Arc<RwLock<String>>,
+    data_end: u16,
+    data_end_buffer: Arc<RwLock<String>>,
+    break_on_data: bool,
+    break_on_unwritten: bool,
+    break_on_illegal: bool,
+    /// Bytes of every instruction executed since reset.
+    executed: AddressSet,
+    /// Executed bytes written afterwards.
+    modified: AddressSet,
+    warn_on_code_write: bool,
+    break_on_code_write: bool,
+    /// Uninitialized addresses already logged, reported once each.
+    reported: AddressSet,
+    warn_on_uninitialized: bool,
+    break_on_uninitialized: bool,
+}
+
+impl Guards {
+    pub fn new() -> Self {
+        Self {
+            last_s: 0xFF,
+            last_opcode: 0,
+            stack_lowest: 0xFF,
+            break_on_stack_wrap: false,
+            break_on_stack_threshold: false,
+            stack_threshold: 0x40,
+            written: AddressSet::new(),
+            data_regions: vec![],
+            data_start: 0,
+            data_start_buffer: Default::default(),
+            data_end: 0,
+            data_end_buffer: Default::default(),
+            break_on_data: false,
+            break_on_unwritten: false,
+            break_on_illegal: false,
+            executed: AddressSet::new(),
+            modified: AddressSet::new(),
+            warn_on_code_write: false,
+            break_on_code_write: false,
+            reported: AddressSet::new(),
+            warn_on_uninitialized: false,
+            break_on_uninitialized: false,
+        }
+    }
+
+    /// `filled` is the RAM after a reset over RAM filled with $FF, `lynx` has been reset over cleared RAM since.
+    /// The bytes that came out the same both times are the ones the loader wrote.
+    pub fn reset(&mut self, lynx: &Lynx, filled: &[u8]) {
+        self.stack_lowest = 0xFF;
+        self.written.clear();
+        self.executed.clear();
+        self.modified.clear();
+        self.reported.clear();
+        for addr in 0..HARDWARE_START {
+            if lynx.ram().get(addr) == filled[addr as usize] {
+                self.written.insert(addr);
+            }
+        }
+        self.sync(lynx);
+    }
+
+    /// Nothing tells how the RAM of a loaded state was written, it all counts as written.
+    pub fn reset_loaded(&mut self, lynx: &Lynx) {
+        self.stack_lowest = 0xFF;
+        self.written.clear();
+        self.executed.clear();
+        self.modified.clear();
+        self.reported.clear();
+        for addr in 0..HARDWARE_START {
+            self.written.insert(addr);
+        }
+        self.sync(lynx);
+    }
+
+    pub fn modified(&self) -> &AddressSet {
+        &self.modified
+    }
+
+    /// Follows changes made outside of normal execution, like stepping, rewinding or loading a state.
+    pub fn sync(&mut self, lynx: &Lynx) {
+        self.last_s = lynx.mikey().cpu().s();
+        self.stack_lowest = self.stack_lowest.min(self.last_s);
+    }
+
+    /// Checks the instruction about to execute at `pc`, making `accesses`, logs what was found and returns true when the session must pause.
+    pub fn check(&mut self, lynx: &Lynx, pc: u16, accesses: &[MemoryAccess], log: &mut LogPane) -> bool {
+        let pause = self.check_stack(lynx, pc, log)
+            | self.check_execution(lynx, pc, log)
+            | self.check_code_write(pc, accesses, log)
+            | self.check_uninitialized(pc, accesses, log);
+        self.track(lynx, pc, accesses);
+        pause
+    }
+
+    /// Records the instruction about to execute at `pc` and the memory it writes among `accesses`.
+    pub fn track(&mut self, lynx: &Lynx, pc: u16, accesses: &[MemoryAccess]) {
+        let mut addr = pc;
+        let end = next_address(lynx, pc);
+        self.last_opcode = lynx.cpu_mem(pc);
+        while addr != end {
+            self.executed.insert(addr);
+            addr = addr.wrapping_add(1);
+        }
+        for access in accesses.iter().filter(|a| a.access != Access::Read) {
+            for addr in access.addresses() {
+                self.written.insert(addr);
+                if self.executed.contains(addr) {
+                    self.modified.insert(addr);
+                }
+            }
+        }
+    }
+
+    /// Records the video and collision buffers Suzy is about to draw into.
+    pub fn track_sprite_chain(&mut self, lynx: &Lynx) {
+        for base in [suzy_word(lynx, VIDBAS), suzy_word(lynx, COLLBAS)] {
+            for i in 0..BUFFER_SIZE {
+                self.written.insert(base.wrapping_add(i));
+            }
+        }
+    }
+
+    /// Records the collision depository of the SCB Suzy processes.
+    pub fn track_scb(&mut self, lynx: &Lynx, scb: u16) {
+        self.written.insert(scb.wrapping_add(suzy_word(lynx, COLLOFF)));
+    }
+
+    fn check_code_write(&self, pc: u16, accesses: &[MemoryAccess], log: &mut LogPane) -> bool {
+        if !self.warn_on_code_write && !self.break_on_code_write {
+            return false;
+        }
+        let overwritten = accesses
+            .iter()
+            .filter(|a| a.access != Access::Read)
+            .flat_map(|a| a.addresses())
+            .filter(|addr| self.executed.contains(*addr));
+        let mut found = false;
+        for addr in overwritten {
+            found = true;
+            // Tracked after the checks, an address already modified was reported by an earlier write.
+            if self.warn_on_code_write && !self.modified.contains(addr) {
+                log.push(format!("Code at ${:04X} overwritten by PC ${:04X}", addr, pc));
+            }
+        }
+        found && self.break_on_code_write
+    }
+
+    fn check_execution(&self, lynx: &Lynx, pc: u16, log: &mut LogPane) -> bool {
+        let mut pause = false;
+        if self.break_on_data && self.data_regions.iter().any(|(start, end)| (*start..=*end).contains(&pc)) {
+            log.push(format!("Executing data at PC ${:04X}", pc));
+            pause = true;
+        }
+        if self.break_on_unwritten && pc < HARDWARE_START && !self.written.contains(pc) {
+            log.push(format!("Executing never written RAM at PC ${:04X}", pc));
+            pause = true;
+        }
+        if self.break_on_illegal && is_illegal(lynx.cpu_mem(pc)) {
+            log.push(format!("Illegal opcode ${:02X} at PC ${:04X}", lynx.cpu_mem(pc), pc));
+            pause = true;
+        }
+        pause
+    }
+
+    /// Compares S with the one of the last check, also called after a single step.
+    pub fn check_stack(&mut self, lynx: &Lynx, pc: u16, log: &mut LogPane) -> bool {
+        let s = lynx.mikey().cpu().s();
+        let last_s = std::mem::replace(&mut self.last_s, s);
+        self.stack_lowest = self.stack_lowest.min(s);
+
+        let mut pause = false;
+        // Interrupts push the return address and P without an instruction reporting it.
+        let pushed = last_s.wrapping_sub(s);
+        if self.last_opcode != TXS && pushed <= MAX_STACK_STEP {
+            for i in 1..=pushed {
+                self.written.insert(0x0100 | s.wrapping_add(i) as u16);
+            }
+        }
+
+        let linear = s.abs_diff(last_s);
+        if self.last_opcode != TXS && linear > MAX_STACK_STEP && (last_s.wrapping_sub(s) <= MAX_STACK_STEP || s.wrapping_sub(last_s) <= MAX_STACK_STEP) {
+            let kind = if s > last_s { "overflow" } else { "underflow" };
+            log.push(format!("Stack {} at PC ${:04X}, S ${:02X} -> ${:02X}", kind, pc, last_s, s));
+            pause |= self.break_on_stack_wrap;
+        }
+        if self.break_on_stack_threshold && last_s >= self.stack_threshold && s < self.stack_threshold {
+            log.push(format!("Stack below ${:02X} at PC ${:04X}, S ${:02X}", self.stack_threshold, pc, s));
+            pause = true;
+        }
+        pause
+    }
+
+    fn check_uninitialized(&mut self, pc: u16, accesses: &[MemoryAccess], log: &mut LogPane) -> bool {
+        if !self.warn_on_uninitialized && !self.break_on_uninitialized {
+            return false;
+        }
+        let written = &self.written;
+        let uninitialized = accesses
+            .iter()
+            .filter(|a| a.access != Access::Write)
+            .flat_map(|a| a.addresses())
+            .filter(|addr| *addr < HARDWARE_START && !written.contains(*addr));
+        let mut found = false;
+        for addr in uninitialized {
+            found = true;
+            if self.warn_on_uninitialized && !self.reported.contains(addr) {
+                self.reported.insert(addr);
+                log.push(format!("Uninitialized read of ${:04X} by PC ${:04X}", addr, pc));
+            }
+        }
+        found && self.break_on_uninitialized
+    }
+
+    pub fn show_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(RichText::new("Guards").strong());
+
+        ui.horizontal(|ui| {
+            ui.monospace(format!("Stack lowest S:${:02X} ({} bytes)", self.stack_lowest, 0xFF - self.stack_lowest as u16));
+            if ui.button("Reset").clicked() {
+                self.stack_lowest = self.last_s;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.break_on_stack_wrap, "Break on wrap");
+            ui.checkbox(&mut self.break_on_stack_threshold, "Break below S");
+            ui.add(DragValue::new(&mut self.stack_threshold).hexadecimal(2, false, true).prefix("$"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Break executing");
+            ui.checkbox(&mut self.break_on_data, "data");
+            ui.checkbox(&mut self.break_on_unwritten, "unwritten RAM");
+            ui.checkbox(&mut self.break_on_illegal, "illegal opcode");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Code write");
+            ui.checkbox(&mut self.warn_on_code_write, "log");
+            ui.checkbox(&mut self.break_on_code_write, "break");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Uninitialized read");
+            ui.checkbox(&mut self.warn_on_uninitialized, "log");
+            ui.checkbox(&mut self.break_on_uninitialized, "break");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Data");
+            hex_input::HexInput::new(&mut self.data_start, self.data_start_buffer.clone(), 8).ui(ui);
+            ui.label("-");
+            hex_input::HexInput::new(&mut self.data_end, self.data_end_buffer.clone(), 9).ui(ui);
+            if ui.button("Add").clicked() {
+                self.data_regions.push((self.data_start, self.data_end.max(self.data_start)));
+            }
+        });
+
+        let mut to_delete: Option<usize> = None;
+        for (i, (start, end)) in self.data_regions.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.monospace(format!("${:04X}-${:04X}", start, end));
+                if ui.add(egui::Button::new("❌").frame(false)).clicked() {
+                    to_delete = Some(i);
+                }
+            });
+        }
+        if let Some(d) = to_delete {
+            self.data_regions.remove(d);
+        }
+    }
+}
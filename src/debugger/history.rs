@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+
+/// Serialized emulator state along with the session counters at that point.
+pub struct Snapshot {
+    pub data: Vec<u8>,
+    pub ticks: u64,
+    pub instructions: u64,
+    pub frames: u64,
+    pub current_pc: u16,
+    pub vbl_counter: u8,
+}
+
+/// Joystick and switches as set before the given tick, replayed on rewind.
+#[derive(Clone, Copy)]
+pub struct InputEvent {
+    pub tick: u64,
+    pub joystick: u8,
+    pub switches: u8,
+}
+
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    inputs: Vec<InputEvent>,
+    capacity: usize,
+    interval: u64,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            inputs: vec![],
+            capacity: 150,
+            interval: 5,
+        }
+    }
+
+    pub fn snapshot_due(&self, frames: u64) -> bool {
+        match self.snapshots.back() {
+            None => true,
+            Some(s) => frames >= s.frames + self.interval,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() >= self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+
+        let oldest = self.snapshots[0].ticks;
+        self.inputs.retain(|i| i.tick >= oldest);
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Frames between two automatic snapshots.
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: u64) {
+        self.interval = interval.max(1);
+    }
+
+    pub fn get(&self, index: usize) -> &Snapshot {
+        &self.snapshots[index]
+    }
+
+    /// Index of the most recent snapshot taken at or before the given instruction.
+    pub fn latest_before(&self, instruction: u64) -> Option<usize> {
+        self.snapshots
+            .iter()
+            .rposition(|s| s.instructions <= instruction)
+    }
+
+    pub fn record_input(&mut self, input: InputEvent) {
+        self.inputs.push(input);
+    }
+
+    pub fn inputs_at(&self, tick: u64) -> impl Iterator<Item = &InputEvent> {
+        let start = self.inputs.partition_point(|i| i.tick < tick);
+        self.inputs[start..].iter().take_while(move |i| i.tick == tick)
+    }
+
+    /// Drops everything recorded after the given point, the timeline diverges from there.
+    pub fn truncate(&mut self, instruction: u64, tick: u64) {
+        while self.snapshots.back().is_some_and(|s| s.instructions > instruction) {
+            self.snapshots.pop_back();
+        }
+        self.inputs.retain(|i| i.tick <= tick);
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.inputs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(instructions: u64, frames: u64) -> Snapshot {
+        Snapshot {
+            data: vec![],
+            ticks: instructions * 10,
+            instructions,
+            frames,
+            current_pc: 0,
+            vbl_counter: 0,
+        }
+    }
+
+    fn input(tick: u64, joystick: u8) -> InputEvent {
+        InputEvent { tick, joystick, switches: 0 }
+    }
+
+    #[test]
+    fn snapshot_due() {
+        let mut history = History::new();
+        assert!(history.snapshot_due(0));
+        history.push(snapshot(0, 0));
+        assert!(!history.snapshot_due(4));
+        assert!(history.snapshot_due(5));
+        history.set_interval(0);
+        assert_eq!(history.interval(), 1);
+        assert!(history.snapshot_due(1));
+    }
+
+    #[test]
+    fn latest_before() {
+        let mut history = History::new();
+        assert_eq!(history.latest_before(10), None);
+        history.push(snapshot(10, 0));
+        history.push(snapshot(20, 5));
+        assert_eq!(history.latest_before(9), None);
+        assert_eq!(history.latest_before(10), Some(0));
+        assert_eq!(history.latest_before(19), Some(0));
+        assert_eq!(history.latest_before(25), Some(1));
+    }
+
+    #[test]
+    fn capacity_drops_oldest() {
+        let mut history = History::new();
+        history.record_input(input(0, 1));
+        history.record_input(input(15, 2));
+        for i in 0..151 {
+            history.push(snapshot(i, i * 5));
+        }
+        assert_eq!(history.len(), 150);
+        assert_eq!(history.get(0).instructions, 1);
+        assert_eq!(history.inputs_at(0).count(), 0);
+        assert_eq!(history.inputs_at(15).count(), 1);
+    }
+
+    #[test]
+    fn inputs_at() {
+        let mut history = History::new();
+        history.record_input(input(10, 1));
+        history.record_input(input(20, 2));
+        history.record_input(input(20, 3));
+        history.record_input(input(30, 4));
+        let joysticks: Vec<u8> = history.inputs_at(20).map(|i| i.joystick).collect();
+        assert_eq!(joysticks, [2, 3]);
+        assert_eq!(history.inputs_at(25).count(), 0);
+    }
+
+    #[test]
+    fn truncate() {
+        let mut history = History::new();
+        history.push(snapshot(0, 0));
+        history.push(snapshot(10, 5));
+        history.push(snapshot(20, 10));
+        history.record_input(input(50, 1));
+        history.record_input(input(150, 2));
+        history.truncate(10, 100);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.inputs_at(50).count(), 1);
+        assert_eq!(history.inputs_at(150).count(), 0);
+        history.clear();
+        assert!(history.is_empty());
+        assert_eq!(history.inputs_at(50).count(), 0);
+    }
+}
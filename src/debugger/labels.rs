@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use egui::{mutex::RwLock, RichText, ScrollArea, TextEdit, Widget};
+use serde::{Deserialize, Serialize};
+
+use super::hex_input;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Label {
+    pub name: String,
+    pub addr: u16,
+}
+
+pub struct Labels {
+    name: String,
+    input: u16,
+    buffer: Arc<RwLock<String>>
+}
+
+impl Labels {
+    pub fn new() -> Self {
+        Self {
+            name: String::new(),
+            input: 0,
+            buffer: Default::default(),
+        }
+    }
+
+    /// Returns true when the labels changed.
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, labels: &mut Vec<Label>) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Labels").strong());
+            ui.add(TextEdit::singleline(&mut self.name).hint_text("name").desired_width(80.0));
+            hex_input::HexInput::new(&mut self.input, self.buffer.clone(), 7).ui(ui);
+            if ui.button("Add").clicked() && !self.name.trim().is_empty() {
+                labels.retain(|l| l.addr != self.input);
+                labels.push(Label { name: self.name.trim().to_string(), addr: self.input });
+                labels.sort_by_key(|l| l.addr);
+                changed = true;
+            }
+        });
+
+        let scroll = ScrollArea::vertical()
+            .id_source("labels_scroll")
+            .max_height(f32::INFINITY)
+            .auto_shrink([false, true]);
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+
+        let mut to_delete: Option<usize> = None;
+
+        scroll.show_rows(ui, row_height, labels.len(), |ui, line_range| {
+            egui::Grid::new("label_grid")
+                .striped(true)
+                .spacing(egui::Vec2::new(8.0, ui.style().spacing.item_spacing.y))
+                .show(ui, |ui| {
+                    let mut current_line = line_range.start;
+
+                    while current_line != line_range.end {
+                        let label = &labels[current_line];
+                        ui.monospace(format!("${:04X}: {}", label.addr, label.name));
+
+                        if ui.add(egui::Button::new("❌").frame(false)).clicked() {
+                            to_delete = Some(current_line);
+                        }
+
+                        ui.end_row();
+                        current_line += 1;
+                    }
+                });
+        });
+
+        if let Some(d) = to_delete {
+            labels.remove(d);
+            changed = true;
+        }
+
+        changed
+    }
+}
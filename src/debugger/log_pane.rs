@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+
+use egui::{RichText, ScrollArea};
+
+const MAX_LINES: usize = 1000;
+
+pub struct LogPane {
+    lines: VecDeque<String>,
+}
+
+impl LogPane {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() >= MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn show_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Log").strong());
+            if ui.button("Clear").clicked() {
+                self.lines.clear();
+            }
+        });
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+
+        ScrollArea::vertical()
+            .id_source("log_scroll")
+            .max_height(100.0)
+            .auto_shrink([false, true])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, self.lines.len(), |ui, line_range| {
+                for line in self.lines.range(line_range) {
+                    ui.monospace(line);
+                }
+            });
+    }
+}
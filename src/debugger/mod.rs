@@ -5,11 +5,18 @@ use settings::Settings;
 use crate::core_runner::{AddCoreConfiguration, CoreRunner};
 mod breakpoints;
 mod disassembler;
+mod expression;
+mod guards;
 mod hex_input;
+mod history;
+mod labels;
+mod log_pane;
+mod project;
 pub mod session;
 pub mod settings;
 mod timers;
 mod watches;
+mod watchpoints;
 
 pub struct Debugger {
     settings: Settings,
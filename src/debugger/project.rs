@@ -0,0 +1,76 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use super::{breakpoints::Breakpoint, labels::Label, watches::Watch, watchpoints::Watchpoint};
+
+/// Debugging state kept per cartridge, stored by confy next to the settings.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Project {
+    pub watches: Vec<Watch>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub watchpoints: Vec<Watchpoint>,
+    pub labels: Vec<Label>,
+}
+
+impl Project {
+    /// Configuration name of a cartridge, keyed by the FNV-1a hash of its content.
+    pub fn name(cart: &[u8]) -> String {
+        let hash = cart.iter().fold(0xcbf29ce484222325u64, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3));
+        format!("cart-{:016x}", hash)
+    }
+
+    /// Falls back to the defaults when the project can't be read, after moving the file aside.
+    /// Returns `None` when it can't be moved either, the project must then not be stored over it.
+    pub fn load(name: &str) -> Option<Self> {
+        match confy::load::<Project>("holani", Some(name)) {
+            Err(e) => {
+                error!("Couldn't load project '{}'. Using defaults. '{}'", name, e);
+                Self::backup(name).then(Project::default)
+            }
+            Ok(mut p) => {
+                p.breakpoints.iter_mut().for_each(|bp| bp.set_condition(bp.condition().to_string(), &p.labels));
+                p.watches.iter_mut().for_each(|w| w.parse(&p.labels));
+                Some(p)
+            }
+        }
+    }
+
+    fn backup(name: &str) -> bool {
+        let path = match confy::get_configuration_file_path("holani", Some(name)) {
+            Err(e) => {
+                error!("Couldn't locate project '{}'. '{}'", name, e);
+                return false;
+            }
+            Ok(path) => path,
+        };
+        let backup = path.with_extension("toml.bak");
+        match std::fs::rename(&path, &backup) {
+            Err(e) => {
+                error!("Couldn't back up project '{}', it won't be saved. '{}'", name, e);
+                false
+            }
+            Ok(_) => {
+                error!("Project '{}' moved to '{}'.", name, backup.display());
+                true
+            }
+        }
+    }
+
+}
+
+/// Borrowed view of the session state, stored with the same layout as `Project`.
+#[derive(Serialize)]
+pub struct ProjectRef<'a> {
+    pub watches: &'a [Watch],
+    pub breakpoints: &'a [Breakpoint],
+    pub watchpoints: &'a [Watchpoint],
+    pub labels: &'a [Label],
+}
+
+impl ProjectRef<'_> {
+    pub fn store(&self, name: &str) {
+        if let Err(e) = confy::store("holani", Some(name), self) {
+            error!("Couldn't save project '{}'. '{}'", name, e);
+        }
+    }
+}
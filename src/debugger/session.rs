@@ -1,8 +1,9 @@
 use std::{f32::consts::FRAC_PI_2, path::PathBuf};
-use egui::{vec2, Color32, RichText, TextureOptions, Vec2, Widget};
+use log::error;
+use egui::{vec2, Color32, DragValue, RichText, Slider, TextureOptions, Vec2, Widget};
 use egui_memory_editor::MemoryEditor;
-use holani::{cartridge::lnx_header::LNXRotation, consts::INTSET, mikey::{cpu::M6502Flags, uart::comlynx_cable_mutex::ComlynxCable, video::RGB_SCREEN_BUFFER_LEN, MikeyBusOwner}, suzy::registers::{Joystick, Switches}, lynx::Lynx};
-use super::{breakpoints::Breakpoints, disassembler::DisasmWidget, settings::Settings, timers::Timers, watches::Watches};
+use holani::{cartridge::lnx_header::LNXRotation, consts::INTSET, mikey::{cpu::{M6502, M6502Flags}, uart::comlynx_cable_mutex::ComlynxCable, video::RGB_SCREEN_BUFFER_LEN, MikeyBusOwner}, suzy::registers::{Joystick, Switches}, lynx::Lynx};
+use super::{breakpoints::{format_message, Breakpoint, Breakpoints}, disassembler::{memory_accesses, DisasmWidget, MemoryAccess}, expression::SymbolContext, guards::Guards, history::{History, InputEvent, Snapshot}, labels::{Label, Labels}, log_pane::LogPane, project::{Project, ProjectRef}, settings::Settings, timers::Timers, watches::{Watch, Watches}, watchpoints::{Watchpoint, Watchpoints}};
 use holani::consts::*;
 
 macro_rules! cond_strong_label {
@@ -31,9 +32,55 @@ pub enum RunnerStatus {
     RunningAsked,
     Running,
     Step,
+    StepOver,
+    StepOut,
+    RunFrames(u32),
+    RunInstructions(u32),
+    RunTicks(u32),
+    StepBack,
+    ReverseContinue,
     Reset,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum RunUntil {
+    /// Instruction following a `JSR`, reached back at the caller's stack level.
+    Return { pc: u16, s: u8 },
+    /// `RTS` or `RTI` popping the stack above the given level, outside of the interrupts taken meanwhile.
+    StackAbove { s: u8, interrupts: u32 },
+    /// First instruction of the given frame.
+    Frame(u64),
+    /// Instruction count reached.
+    Instruction(u64),
+    /// Exact crystal tick, checked between ticks rather than instructions.
+    Tick(u64),
+}
+
+/// Backwards search of a reverse continue, one snapshot segment replayed at a time.
+struct ReverseSearch {
+    /// Instruction count the search started from, restored when cancelled.
+    origin: u64,
+    /// Snapshot the segment being replayed starts from.
+    index: usize,
+    /// Instruction count the segment ends at.
+    end: u64,
+    /// Last breakpoint hit found in the segment.
+    hit: Option<u64>,
+}
+
+const BRK: u8 = 0x00;
+const JSR: u8 = 0x20;
+const RTS: u8 = 0x60;
+const RTI: u8 = 0x40;
+const IRQ_VECTOR: u16 = 0xFFFE;
+/// SCBADR, the SCB Suzy is currently processing.
+const SCB_ADDRESS: u16 = 0xFC2C;
+const INTERRUPT_NAMES: [&str; 8] = ["Timer 0 (HBL)", "Timer 1", "Timer 2 (VBL)", "Timer 3", "Timer 4 (Serial)", "Timer 5", "Timer 6", "Timer 7"];
+/// Timer 2 drives the vertical blank, its counter reloads once per frame.
+const VBL_COUNTER: u16 = TIM0BKUP + 2 * 4 + 2;
+/// Instructions replayed per step while reverse continuing, keeps the UI responsive.
+const REVERSE_CHUNK: u32 = 10_000;
+
 pub struct LynxSession {
     thread_nr: usize,
     lynx: Lynx,
@@ -41,10 +88,46 @@ pub struct LynxSession {
     timers: Timers,
     ram: MemoryEditor,
     status: RunnerStatus,
-    breakpoints: Vec<(bool, u16)>,
+    run_until: Option<RunUntil>,
+    /// Run to cursor target, kept across other stops until reached or cancelled.
+    run_to: Option<u16>,
+    current_pc: u16,
+    /// Opcode fetch state of the CPU on the last tick, an instruction starts on its rising edge.
+    opcode_fetch: bool,
+    /// Instruction count of the last instruction checked for watchpoints and guards before it ran.
+    checked: Option<u64>,
+    frames: u64,
+    vbl_counter: u8,
+    frames_to_run: u32,
+    instructions: u64,
+    instructions_to_run: u32,
+    ticks_to_run: u32,
+    run_start_ticks: u64,
+    history: History,
+    timeline_index: usize,
+    reverse_search: Option<ReverseSearch>,
+    breakpoints: Vec<Breakpoint>,
     breakpoints_edit: Breakpoints,
-    watches: Vec<u16>,
+    log: LogPane,
+    watchpoints: Vec<Watchpoint>,
+    watchpoints_edit: Watchpoints,
+    last_access: Option<(u16, MemoryAccess)>,
+    guards: Guards,
+    interrupt_breaks: u8,
+    bus_grant: bool,
+    /// SCB Suzy is processing, `None` until the first one of the current sprite chain.
+    scb_addr: Option<u16>,
+    break_on_sprites_start: bool,
+    break_on_sprites_end: bool,
+    break_on_scb: bool,
+    sprite_event: Option<String>,
+    last_ticks: u64,
+    last_frames: u64,
+    watches: Vec<Watch>,
     watches_edit: Watches,
+    labels: Vec<Label>,
+    labels_edit: Labels,
+    project_name: Option<String>,
     joystick: Joystick,
     switches: Switches,
     rotation: LNXRotation,
@@ -63,10 +146,42 @@ impl LynxSession {
                 .with_address_range("All", 0..0xFFFF+1)
                 .with_window_title("RAM"),
             status: RunnerStatus::Paused,
+            run_until: None,
+            run_to: None,
+            current_pc: 0,
+            opcode_fetch: false,
+            checked: None,
+            frames: 0,
+            vbl_counter: 0,
+            frames_to_run: 1,
+            instructions: 0,
+            instructions_to_run: 1,
+            ticks_to_run: 1,
+            run_start_ticks: 0,
+            history: History::new(),
+            timeline_index: 0,
+            reverse_search: None,
             breakpoints: vec![],
             breakpoints_edit: Breakpoints::new(),
+            log: LogPane::new(),
+            watchpoints: vec![],
+            watchpoints_edit: Watchpoints::new(),
+            last_access: None,
+            guards: Guards::new(),
+            interrupt_breaks: 0,
+            bus_grant: true,
+            scb_addr: None,
+            break_on_sprites_start: false,
+            break_on_sprites_end: false,
+            break_on_scb: false,
+            sprite_event: None,
+            last_ticks: 0,
+            last_frames: 0,
             watches: vec![],
             watches_edit: Watches::new(),
+            labels: vec![],
+            labels_edit: Labels::new(),
+            project_name: None,
             joystick: Joystick::empty(),
             switches: Switches::empty(),
             rotation: LNXRotation::None,
@@ -122,9 +237,37 @@ impl LynxSession {
     fn right_panel(&mut self, ui: &mut egui::Ui) {
         self.palette_show(ui);
         ui.separator();
-        self.breakpoints_edit.show_ui(ui, &mut self.breakpoints);
+        let mut project_changed = self.breakpoints_edit.show_ui(ui, &mut self.breakpoints, &self.labels);
+        ui.separator();
+        self.log.show_ui(ui);
         ui.separator();
-        self.watches_edit.show_ui(ui, &mut self.watches, self.lynx.ram());
+        project_changed |= self.watchpoints_edit.show_ui(ui, &mut self.watchpoints, self.last_access);
+        ui.separator();
+        self.guards.show_ui(ui);
+        ui.separator();
+        let paused = self.status == RunnerStatus::Paused;
+        let ctx = SymbolContext { lynx: &self.lynx, labels: &self.labels };
+        let response = self.watches_edit.show_ui(ui, &mut self.watches, &ctx, paused);
+        project_changed |= response.changed;
+        if let Some((addr, bytes)) = response.edit {
+            for (i, v) in bytes.into_iter().enumerate() {
+                self.lynx.ram_mut().set(addr.wrapping_add(i as u16), v);
+            }
+        }
+        if response.edit_done {
+            self.commit_edit();
+        }
+        ui.separator();
+        if self.labels_edit.show_ui(ui, &mut self.labels) {
+            self.disassembler.set_labels(&self.labels);
+            // Conditions and watches naming a label that went away become errors, and the other way round.
+            self.breakpoints.iter_mut().for_each(|bp| bp.set_condition(bp.condition().to_string(), &self.labels));
+            self.watches.iter_mut().for_each(|w| w.parse(&self.labels));
+            project_changed = true;
+        }
+        if project_changed {
+            self.store_project();
+        }
         ui.separator();
         ui.horizontal(|ui| {
             self.interrupts_show(ui);
@@ -132,11 +275,34 @@ impl LynxSession {
             self.bus_show(ui);
         });        
         ui.separator();
-        self.timers.show(self.lynx.mikey().timers(), ui);
+        let (poke, edit_done) = self.timers.show(self.lynx.mikey().timers(), ui, paused);
+        if let Some((addr, v)) = poke {
+            self.lynx.mikey_mut().timers_mut().poke(addr, v);
+        }
+        if edit_done {
+            self.commit_edit();
+        }
     }
 
-    fn top_panel(&mut self, _ui: &mut egui::Ui) {
-        
+    fn top_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Timeline");
+            if self.history.is_empty() {
+                ui.label("-");
+            } else {
+                let mut index = self.timeline_index.min(self.history.len() - 1);
+                if ui.add(Slider::new(&mut index, 0..=self.history.len() - 1).show_value(false)).changed() {
+                    self.timeline_index = index;
+                    self.restore_snapshot(index);
+                    self.pause();
+                }
+                ui.monospace(format!("frame: {}", self.history.get(index).frames));
+            }
+            let mut interval = self.history.interval();
+            if ui.add(DragValue::new(&mut interval).suffix(" frames/snapshot")).changed() {
+                self.history.set_interval(interval);
+            }
+        });
     }
 
     fn left_panel(&mut self, ui: &mut egui::Ui) {
@@ -148,7 +314,10 @@ impl LynxSession {
         ui.separator();
         self.buttons_show(ui);
         ui.separator();
-        self.disassembler.disasm_show(ui, self.lynx.mikey().cpu().last_ir_pc, &self.lynx);
+        if let Some(addr) = self.disassembler.disasm_show(ui, self.lynx.mikey().cpu().last_ir_pc, &self.lynx, self.guards.modified()) {
+            self.run_to = Some(addr);
+            self.status = RunnerStatus::RunningAsked;
+        }
     }
 
     fn central_panel(&mut self, ui: &mut egui::Ui) {
@@ -180,6 +349,16 @@ impl LynxSession {
             cond_strong_label!(ui, "1", ints & 2 != 0);
             cond_strong_label!(ui, "0", ints & 1 != 0);
         });
+        ui.horizontal(|ui| {
+            for i in (0..8).rev() {
+                let mut brk = self.interrupt_breaks & (1 << i) != 0;
+                if ui.checkbox(&mut brk, "")
+                        .on_hover_text(format!("Break on {}", INTERRUPT_NAMES[i]))
+                        .changed() {
+                    self.interrupt_breaks ^= 1 << i;
+                }
+            }
+        });
     }
 
     fn bus_show(&mut self, ui: &mut egui::Ui) {
@@ -194,37 +373,110 @@ impl LynxSession {
                 cond_strong_label!(ui, "Refresh/Video", grant && owner == MikeyBusOwner::RefreshAndVideo);    
             });
             cond_strong_label!(ui, "Suzy", !grant);
+            ui.horizontal(|ui| {
+                ui.label("Break on");
+                ui.checkbox(&mut self.break_on_sprites_start, "start")
+                    .on_hover_text("Suzy takes the bus to process the sprite chain");
+                ui.checkbox(&mut self.break_on_sprites_end, "end")
+                    .on_hover_text("Suzy hands the bus back");
+                ui.checkbox(&mut self.break_on_scb, "SCB")
+                    .on_hover_text("Suzy moves to the next SCB");
+            });
         });        
     }
 
     fn cpu_show(&mut self, ui: &mut egui::Ui) {
         let ticks = self.lynx.ticks();
         let cpu = self.lynx.mikey().cpu();
+        let (mut edit, mut edit_done) = (None, false);
         ui.vertical(|ui| {
             ui.label(RichText::new("CPU").strong());
-            ui.monospace(format!("A:${:02X} X:${:02X} Y:${:02X}", cpu.a(), cpu.x(), cpu.y()))
-                .on_hover_ui(|ui| {
-                    let a = cpu.a();
-                    let x = cpu.x();
-                    let y = cpu.y();
-                    ui.monospace(format!("A:${:02X} b{:08b} {:03}", a, a, a));
-                    ui.monospace(format!("X:${:02X} b{:08b} {:03}", x, x, x));
-                    ui.monospace(format!("Y:${:02X} b{:08b} {:03}", y, y, y));
+            if self.status == RunnerStatus::Paused {
+                (edit, edit_done) = Self::registers_edit(ui, cpu);
+            } else {
+                ui.monospace(format!("A:${:02X} X:${:02X} Y:${:02X}", cpu.a(), cpu.x(), cpu.y()))
+                    .on_hover_ui(|ui| {
+                        let a = cpu.a();
+                        let x = cpu.x();
+                        let y = cpu.y();
+                        ui.monospace(format!("A:${:02X} b{:08b} {:03}", a, a, a));
+                        ui.monospace(format!("X:${:02X} b{:08b} {:03}", x, x, x));
+                        ui.monospace(format!("Y:${:02X} b{:08b} {:03}", y, y, y));
+                    });
+                ui.monospace(format!("S:${:02X} PC:${:04X}", cpu.s(), cpu.pc()));
+            }
+            ui.monospace(format!("ticks: {} (+{})", ticks, ticks.saturating_sub(self.run_start_ticks)));
+            ui.monospace(format!("instr: {}", self.instructions));
+            ui.monospace(format!("frame: {}", self.frames));
+            if self.status != RunnerStatus::Paused {
+                ui.horizontal(|ui| {
+                    let flags = cpu.flags();
+                    cond_strong_label!(ui, "N", flags.contains(M6502Flags::N));
+                    cond_strong_label!(ui, "V", flags.contains(M6502Flags::V));
+                    cond_strong_label!(ui, "X", flags.contains(M6502Flags::X));
+                    cond_strong_label!(ui, "B", flags.contains(M6502Flags::B));
+                    cond_strong_label!(ui, "D", flags.contains(M6502Flags::D));
+                    cond_strong_label!(ui, "I", flags.contains(M6502Flags::I));
+                    cond_strong_label!(ui, "Z", flags.contains(M6502Flags::Z));
+                    cond_strong_label!(ui, "C", flags.contains(M6502Flags::C));
                 });
-            ui.monospace(format!("S:${:02X} PC:${:04X}", cpu.s(), cpu.pc()));
-            ui.monospace(format!("ticks: {}", ticks));
-            ui.horizontal(|ui| {
-                let flags = cpu.flags();
-                cond_strong_label!(ui, "N", flags.contains(M6502Flags::N));
-                cond_strong_label!(ui, "V", flags.contains(M6502Flags::V));
-                cond_strong_label!(ui, "X", flags.contains(M6502Flags::X));
-                cond_strong_label!(ui, "B", flags.contains(M6502Flags::B));
-                cond_strong_label!(ui, "D", flags.contains(M6502Flags::D));
-                cond_strong_label!(ui, "I", flags.contains(M6502Flags::I));
-                cond_strong_label!(ui, "Z", flags.contains(M6502Flags::Z));
-                cond_strong_label!(ui, "C", flags.contains(M6502Flags::C));
-            });
+            }
         });        
+
+        if let Some((a, x, y, s, pc, flags)) = edit {
+            let cpu = self.lynx.mikey_mut().cpu_mut();
+            cpu.set_a(a);
+            cpu.set_x(x);
+            cpu.set_y(y);
+            cpu.set_s(s);
+            cpu.set_flags(flags);
+            if pc != cpu.pc() {
+                // The next instruction is the one at the new PC, for the disassembly and the breakpoints too.
+                cpu.set_pc(pc);
+                cpu.last_ir_pc = pc;
+                self.current_pc = pc;
+                self.checked = None;
+            }
+        }
+        if edit_done {
+            self.commit_edit();
+        }
+    }
+
+    /// Editable copy of the registers, returned when the user changed one, along with true once the edit is over.
+    /// `B` and `X` only exist on the stack, they are shown read-only.
+    fn registers_edit(ui: &mut egui::Ui, cpu: &M6502) -> (Option<(u8, u8, u8, u8, u16, M6502Flags)>, bool) {
+        let (mut a, mut x, mut y, mut s, mut pc, mut flags) = (cpu.a(), cpu.x(), cpu.y(), cpu.s(), cpu.pc(), cpu.flags());
+        let (mut changed, mut done) = (false, false);
+        ui.horizontal(|ui| {
+            for (name, v) in [("A", &mut a), ("X", &mut x), ("Y", &mut y), ("S", &mut s)] {
+                let response = ui.add(DragValue::new(v).hexadecimal(2, false, true).prefix(format!("{}:$", name)));
+                changed |= response.changed();
+                done |= response.drag_stopped() || response.lost_focus();
+            }
+            let response = ui.add(DragValue::new(&mut pc).hexadecimal(4, false, true).prefix("PC:$"));
+            changed |= response.changed();
+            done |= response.drag_stopped() || response.lost_focus();
+        });
+        ui.horizontal(|ui| {
+            for (name, flag) in [("N", M6502Flags::N), ("V", M6502Flags::V), ("X", M6502Flags::X), ("B", M6502Flags::B), ("D", M6502Flags::D), ("I", M6502Flags::I), ("Z", M6502Flags::Z), ("C", M6502Flags::C)] {
+                if matches!(name, "X" | "B") {
+                    cond_strong_label!(ui, name, flags.contains(flag));
+                } else if ui.selectable_label(flags.contains(flag), name).clicked() {
+                    flags.toggle(flag);
+                    changed = true;
+                    done = true;
+                }
+            }
+        });
+        (changed.then_some((a, x, y, s, pc, flags)), done)
+    }
+
+    /// Keeps a value changed by the user when rewinding across it.
+    fn commit_edit(&mut self) {
+        self.history.truncate(self.instructions.saturating_sub(1), self.lynx.ticks());
+        self.take_snapshot();
+        self.guards.sync(&self.lynx);
     }
 
     fn controls_show(&mut self, ui: &mut egui::Ui) {
@@ -239,7 +491,12 @@ impl LynxSession {
                     RunnerStatus::Running => if ui.button("⏸")
                             .on_hover_text("Pause")
                             .clicked() {
-                        self.status = RunnerStatus::Paused;
+                        self.pause();
+                    }
+                    RunnerStatus::ReverseContinue => if ui.button("⏸")
+                            .on_hover_text("Cancel reverse continue")
+                            .clicked() {
+                        self.cancel_reverse_continue();
                     }
                     _ => { let _ = ui.button("-"); } ,
                 }
@@ -248,12 +505,71 @@ impl LynxSession {
                         .clicked() {
                     self.status = RunnerStatus::Step;
                 }
+                if ui.button("⤵")
+                        .on_hover_text("Step over")
+                        .clicked() {
+                    self.status = RunnerStatus::StepOver;
+                }
+                if ui.button("⤴")
+                        .on_hover_text("Step out")
+                        .clicked() {
+                    self.status = RunnerStatus::StepOut;
+                }
+                if ui.button("⏮")
+                        .on_hover_text("Step back")
+                        .clicked() {
+                    self.status = RunnerStatus::StepBack;
+                }
+                if ui.button("⏪")
+                        .on_hover_text("Reverse continue")
+                        .clicked() {
+                    self.status = RunnerStatus::ReverseContinue;
+                }
                 if ui.button("⟲")
                         .on_hover_text("Reset")
                         .clicked() {
                     self.status = RunnerStatus::Reset;
                 }
             });
+            if let Some(addr) = self.run_to {
+                ui.horizontal(|ui| {
+                    ui.monospace(format!("Run to ${:04X}", addr));
+                    if ui.add(egui::Button::new("❌").frame(false))
+                            .on_hover_text("Cancel run to cursor")
+                            .clicked() {
+                        self.run_to = None;
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui.button("🎞")
+                        .on_hover_text("Next frame")
+                        .clicked() {
+                    self.status = RunnerStatus::RunFrames(1);
+                }
+                ui.add(DragValue::new(&mut self.frames_to_run).suffix(" frames"));
+                if ui.button("⏩")
+                        .on_hover_text("Run frames")
+                        .clicked() {
+                    self.status = RunnerStatus::RunFrames(self.frames_to_run.max(1));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut self.instructions_to_run).suffix(" instr."));
+                if ui.button("⏩")
+                        .on_hover_text("Run instructions")
+                        .clicked() {
+                    self.status = RunnerStatus::RunInstructions(self.instructions_to_run.max(1));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut self.ticks_to_run).suffix(" ticks"));
+                if ui.button("⏩")
+                        .on_hover_text("Run ticks")
+                        .clicked() {
+                    self.status = RunnerStatus::RunTicks(self.ticks_to_run.max(1));
+                }
+            });
             ui.horizontal(|ui| {
                 if ui.button("📁")
                         .on_hover_text("Save state")
@@ -278,13 +594,11 @@ impl LynxSession {
                         .set_title("Lynx state")
                         .pick_file() {
                             match std::fs::read(path) {
-                                Err(e) => println!("deserialization error: {:?}", e),
-                                Ok(data) => match holani::deserialize(&data, &self.lynx) {
-                                    Err(e) => println!("deserialization error: {:?}", e),
-                                    Ok(mut lynx) => {
-                                        lynx.set_comlynx_cable(&self.lynx.comlynx_cable().clone());
-                                        self.lynx = lynx;
-                                    },
+                                Err(e) => error!("Couldn't read state. '{}'", e),
+                                Ok(data) => if let Some(lynx) = self.deserialize_state(&data) {
+                                    self.replace_lynx(lynx);
+                                    self.guards.reset_loaded(&self.lynx);
+                                    self.restart_counters();
                                 }
                             };
                     }
@@ -336,29 +650,474 @@ impl LynxSession {
     pub fn step(&mut self) {
         match self.status {
             RunnerStatus::RunningAsked => {
-                self.lynx.tick();
+                self.history.truncate(self.instructions, self.lynx.ticks());
+                self.ensure_baseline();
+                self.run_start_ticks = self.lynx.ticks();
                 self.status = RunnerStatus::Running;
+                // The instruction paused on started without a rising edge to catch, unless it was checked then.
+                if self.checked != Some(self.instructions) && self.check_instruction() {
+                    self.pause();
+                    return;
+                }
+                if self.tick() {
+                    self.sample_watches();
+                }
             }
             RunnerStatus::Running => {
-                let instr_pc = self.lynx.mikey().cpu().last_ir_pc;
-                if self.breakpoints.iter().any(|(en, addr)| { *en && *addr == instr_pc }) {
-                    self.status = RunnerStatus::Paused;
-                } else {
-                    self.lynx.tick();
+                let previous_pc = self.current_pc;
+                let new_instruction = self.track_instruction();
+                if new_instruction && self.history.snapshot_due(self.frames) {
+                    self.take_snapshot();
+                }
+                if let Some(event) = self.sprite_event.take() {
+                    self.log.push(event);
+                    self.pause();
+                    return;
+                }
+                let mut pause = self.breakpoint_hit(new_instruction);
+                if new_instruction {
+                    pause |= self.check_instruction();
+                    pause |= self.interrupt_hit(previous_pc);
+                }
+                // Not short-circuited, run to cursor is consumed even when something else stops on the same instruction.
+                let reached = self.run_until_reached(new_instruction, previous_pc) | self.run_to_reached(new_instruction);
+                if pause || reached {
+                    self.pause();
+                    return;
+                }
+                if self.tick() {
+                    self.sample_watches();
                 }
             }
             RunnerStatus::Step => {
-                self.lynx.step_instruction();
-                self.status = RunnerStatus::Paused;
+                self.run_start_ticks = self.lynx.ticks();
+                self.step_instruction();
+                self.pause();
+            }
+            RunnerStatus::StepOver => {
+                let (pc, s) = {
+                    let cpu = self.lynx.mikey().cpu();
+                    (cpu.last_ir_pc, cpu.s())
+                };
+                if self.lynx.cpu_mem(pc) == JSR {
+                    self.run_until = Some(RunUntil::Return { pc: pc.wrapping_add(3), s });
+                    self.status = RunnerStatus::RunningAsked;
+                } else {
+                    self.run_start_ticks = self.lynx.ticks();
+                    self.step_instruction();
+                    self.pause();
+                }
+            }
+            RunnerStatus::StepOut => {
+                self.run_until = Some(RunUntil::StackAbove { s: self.lynx.mikey().cpu().s(), interrupts: 0 });
+                self.status = RunnerStatus::RunningAsked;
+            }
+            RunnerStatus::RunFrames(count) => {
+                self.run_until = Some(RunUntil::Frame(self.frames + count as u64));
+                self.status = RunnerStatus::RunningAsked;
+            }
+            RunnerStatus::RunInstructions(count) => {
+                self.run_until = Some(RunUntil::Instruction(self.instructions + count as u64));
+                self.status = RunnerStatus::RunningAsked;
+            }
+            RunnerStatus::RunTicks(count) => {
+                self.run_until = Some(RunUntil::Tick(self.lynx.ticks() + count as u64));
+                self.status = RunnerStatus::RunningAsked;
+            }
+            RunnerStatus::StepBack => {
+                if self.instructions == 0 || !self.rewind_to(self.instructions - 1) {
+                    self.log.push("Nothing to step back to".to_string());
+                }
+                self.pause();
+            }
+            RunnerStatus::ReverseContinue => {
+                if self.reverse_continue() {
+                    self.pause();
+                }
             }
             RunnerStatus::Reset => {
-                self.lynx.reset();
-                self.status = RunnerStatus::Paused;
+                self.reset_lynx();
+                self.restart_counters();
             }
             RunnerStatus::Paused => ()
         };
     }
 
+    /// Returns true when a new frame started.
+    fn tick(&mut self) -> bool {
+        self.lynx.tick();
+        let frame = self.track_frame();
+        self.track_sprites();
+        frame
+    }
+
+    fn step_instruction(&mut self) {
+        self.history.truncate(self.instructions, self.lynx.ticks());
+        self.ensure_baseline();
+        let pc = self.lynx.mikey().cpu().last_ir_pc;
+        let accesses = memory_accesses(&self.lynx, pc);
+        self.guards.track(&self.lynx, pc, &accesses);
+        self.lynx.step_instruction();
+        self.guards.check_stack(&self.lynx, pc, &mut self.log);
+        self.instructions += 1;
+        if self.track_frame() {
+            self.sample_watches();
+        }
+        self.track_sprites();
+    }
+
+    /// Follows the bus grant, Suzy owns the bus while it processes the sprite chain.
+    fn track_sprites(&mut self) {
+        let grant = self.lynx.bus().grant();
+        if grant != self.bus_grant {
+            self.bus_grant = grant;
+            if !grant {
+                // A new chain reports its first SCB even when it starts where the previous one did.
+                self.scb_addr = None;
+                self.guards.track_sprite_chain(&self.lynx);
+                if self.break_on_sprites_start {
+                    self.sprite_event = Some(format!("Suzy started the sprite chain at frame {}", self.frames));
+                }
+            } else if self.break_on_sprites_end {
+                self.sprite_event = Some(format!("Suzy finished the sprite chain at frame {}", self.frames));
+            }
+        }
+
+        if !grant {
+            let regs = self.lynx.suzy().registers();
+            let scb = (regs.data(SCB_ADDRESS) as u16) | ((regs.data(SCB_ADDRESS + 1) as u16) << 8);
+            if self.scb_addr != Some(scb) {
+                self.scb_addr = Some(scb);
+                self.guards.track_scb(&self.lynx, scb);
+                if self.break_on_scb {
+                    self.sprite_event = Some(format!("Suzy processing SCB ${:04X}", scb));
+                }
+            }
+        }
+    }
+
+    /// Counts the opcode fetches rather than PC changes so that an instruction jumping to itself still counts.
+    fn track_instruction(&mut self) -> bool {
+        let cpu = self.lynx.mikey().cpu();
+        let fetch = cpu.sync();
+        let started = fetch && !self.opcode_fetch;
+        self.opcode_fetch = fetch;
+        if started {
+            self.current_pc = cpu.last_ir_pc;
+            self.instructions += 1;
+        }
+        started
+    }
+
+    /// Counts the hits of the matching breakpoints and prints their logpoints, true when one must pause.
+    fn breakpoint_hit(&mut self, new_instruction: bool) -> bool {
+        let pc = new_instruction.then_some(self.current_pc);
+        let ticks = self.lynx.ticks();
+        let elapsed_ticks = self.last_ticks + 1..ticks + 1;
+        let elapsed_frames = self.last_frames + 1..self.frames + 1;
+        self.last_ticks = ticks;
+        self.last_frames = self.frames;
+
+        let ctx = SymbolContext { lynx: &self.lynx, labels: &self.labels };
+        let mut pause = false;
+        let mut fired: Vec<usize> = vec![];
+        for (i, bp) in self.breakpoints.iter_mut().enumerate() {
+            if !bp.matches(pc, elapsed_ticks.clone(), elapsed_frames.clone(), &ctx) {
+                continue;
+            }
+            bp.hits += 1;
+            if bp.hits <= bp.ignore {
+                continue;
+            }
+            if bp.logpoint {
+                self.log.push(format_message(&bp.message, &self.labels, &ctx));
+            } else {
+                pause = true;
+            }
+            if bp.temporary {
+                fired.push(i);
+            }
+        }
+        if !fired.is_empty() {
+            for i in fired.into_iter().rev() {
+                self.breakpoints.remove(i);
+            }
+            self.store_project();
+        }
+        pause
+    }
+
+    /// Side-effect free check used when searching the history.
+    fn breakpoint_matches(&self) -> bool {
+        let ctx = SymbolContext { lynx: &self.lynx, labels: &self.labels };
+        self.breakpoints.iter().any(|bp| !bp.logpoint && bp.matches(Some(self.current_pc), 0..0, 0..0, &ctx))
+    }
+
+    /// Watchpoints and guards of the instruction about to execute at `current_pc`, true when one must pause.
+    fn check_instruction(&mut self) -> bool {
+        self.checked = Some(self.instructions);
+        let accesses = memory_accesses(&self.lynx, self.current_pc);
+        self.watchpoint_hit(&accesses) | self.guards.check(&self.lynx, self.current_pc, &accesses, &mut self.log)
+    }
+
+    /// `accesses` are the ones of the instruction about to execute.
+    fn watchpoint_hit(&mut self, accesses: &[MemoryAccess]) -> bool {
+        if self.watchpoints.is_empty() {
+            return false;
+        }
+        let hit = accesses
+            .iter()
+            .find(|access| self.watchpoints.iter().any(|wp| wp.matches(access)))
+            .copied();
+        match hit {
+            Some(access) => {
+                self.last_access = Some((self.current_pc, access));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// True on the first instruction of the IRQ handler when one of the selected INTSET bits is pending.
+    fn interrupt_hit(&self, previous_pc: u16) -> bool {
+        if self.interrupt_breaks == 0 {
+            return false;
+        }
+        self.current_pc == self.irq_handler()
+            && self.lynx.cpu_mem(previous_pc) != BRK
+            && self.lynx.mikey().registers().data(INTSET) & self.interrupt_breaks != 0
+    }
+
+    fn irq_handler(&self) -> u16 {
+        (self.lynx.cpu_mem(IRQ_VECTOR) as u16) | ((self.lynx.cpu_mem(IRQ_VECTOR + 1) as u16) << 8)
+    }
+
+    /// Returns true when a new frame started.
+    fn track_frame(&mut self) -> bool {
+        let counter = self.lynx.mikey().timers().peek(VBL_COUNTER);
+        let frame = counter > self.vbl_counter;
+        if frame {
+            self.frames += 1;
+        }
+        self.vbl_counter = counter;
+        frame
+    }
+
+    /// Only called when running forward, replayed frames were already sampled.
+    fn sample_watches(&mut self) {
+        let ctx = SymbolContext { lynx: &self.lynx, labels: &self.labels };
+        self.watches_edit.sample(&mut self.watches, &ctx, self.frames);
+    }
+
+    /// Resets twice, over RAM filled with $FF then cleared, telling apart the bytes the loader writes.
+    /// The other bytes get back what the RAM held before, a reset doesn't clear it.
+    fn reset_lynx(&mut self) {
+        let original = self.ram_contents();
+        self.fill_ram(0xFF);
+        self.lynx.reset();
+        let filled = self.ram_contents();
+        self.fill_ram(0x00);
+        self.lynx.reset();
+        self.guards.reset(&self.lynx, &filled);
+        for addr in 0..=0xFFFF {
+            if self.lynx.ram().get(addr) != filled[addr as usize] {
+                self.lynx.ram_mut().set(addr, original[addr as usize]);
+            }
+        }
+    }
+
+    fn ram_contents(&self) -> Vec<u8> {
+        (0..=0xFFFF).map(|addr| self.lynx.ram().get(addr)).collect()
+    }
+
+    fn fill_ram(&mut self, value: u8) {
+        for addr in 0..=0xFFFF {
+            self.lynx.ram_mut().set(addr, value);
+        }
+    }
+
+    /// Counts frames and instructions from zero after a reset or a loaded state, the history doesn't apply anymore.
+    fn restart_counters(&mut self) {
+        self.history.clear();
+        self.frames = 0;
+        self.instructions = 0;
+        self.checked = None;
+        self.vbl_counter = self.lynx.mikey().timers().peek(VBL_COUNTER);
+        self.pause();
+        self.take_snapshot();
+    }
+
+    /// The first snapshot is the point the timeline can't be rewound past.
+    fn ensure_baseline(&mut self) {
+        if self.history.is_empty() {
+            self.take_snapshot();
+        }
+    }
+
+    fn pause(&mut self) {
+        self.status = RunnerStatus::Paused;
+        self.run_until = None;
+        self.reverse_search = None;
+        self.sprite_event = None;
+        self.last_ticks = self.lynx.ticks();
+        self.last_frames = self.frames;
+        self.current_pc = self.lynx.mikey().cpu().last_ir_pc;
+        self.opcode_fetch = self.lynx.mikey().cpu().sync();
+        self.guards.sync(&self.lynx);
+    }
+
+    fn take_snapshot(&mut self) {
+        let mut data: Vec<u8> = vec![0; self.lynx.serialize_size()];
+        if holani::serialize(&self.lynx, data.as_mut_slice()).is_err() {
+            return;
+        }
+        self.history.push(Snapshot {
+            data,
+            ticks: self.lynx.ticks(),
+            instructions: self.instructions,
+            frames: self.frames,
+            current_pc: self.current_pc,
+            vbl_counter: self.vbl_counter,
+        });
+        self.timeline_index = self.history.len() - 1;
+    }
+
+    fn deserialize_state(&self, data: &[u8]) -> Option<Lynx> {
+        match holani::deserialize(data, &self.lynx) {
+            Err(e) => {
+                error!("Couldn't deserialize state. '{:?}'", e);
+                None
+            }
+            Ok(mut lynx) => {
+                lynx.set_comlynx_cable(&self.lynx.comlynx_cable().clone());
+                Some(lynx)
+            }
+        }
+    }
+
+    fn replace_lynx(&mut self, lynx: Lynx) {
+        self.lynx = lynx;
+        self.screen_buffer.copy_from_slice(self.lynx.screen_rgb().as_slice());
+    }
+
+    fn restore_snapshot(&mut self, index: usize) {
+        let snapshot = self.history.get(index);
+        let counters = (snapshot.instructions, snapshot.frames, snapshot.current_pc, snapshot.vbl_counter);
+        if let Some(lynx) = self.deserialize_state(&snapshot.data) {
+            self.replace_lynx(lynx);
+            (self.instructions, self.frames, self.current_pc, self.vbl_counter) = counters;
+            self.opcode_fetch = self.lynx.mikey().cpu().sync();
+        }
+    }
+
+    /// Ticks once while re-applying the recorded inputs, returns true when a new instruction starts.
+    fn replay_tick(&mut self) -> bool {
+        for input in self.history.inputs_at(self.lynx.ticks()) {
+            self.lynx.set_joystick_u8(input.joystick);
+            self.lynx.set_switches_u8(input.switches);
+        }
+        self.tick();
+        self.track_instruction()
+    }
+
+    /// Returns false when no snapshot precedes the instruction.
+    fn rewind_to(&mut self, instruction: u64) -> bool {
+        let Some(index) = self.history.latest_before(instruction) else {
+            return false;
+        };
+        self.restore_snapshot(index);
+        while self.instructions < instruction {
+            self.replay_tick();
+        }
+        true
+    }
+
+    /// Replays the snapshots backwards looking for the last breakpoint hit before the current instruction.
+    /// Runs a chunk of instructions per call, returns true once the search is over.
+    fn reverse_continue(&mut self) -> bool {
+        let Some(mut search) = self.reverse_search.take() else {
+            let origin = self.instructions;
+            let Some(index) = origin.checked_sub(1).and_then(|i| self.history.latest_before(i)) else {
+                self.log.push("Nothing to reverse continue to".to_string());
+                return true;
+            };
+            self.restore_snapshot(index);
+            self.reverse_search = Some(ReverseSearch { origin, index, end: origin, hit: None });
+            return false;
+        };
+
+        for _ in 0..REVERSE_CHUNK {
+            if self.instructions >= search.end {
+                break;
+            }
+            if self.breakpoint_matches() {
+                search.hit = Some(self.instructions);
+            }
+            while !self.replay_tick() {}
+        }
+        if self.instructions < search.end {
+            self.reverse_search = Some(search);
+            return false;
+        }
+
+        if let Some(instruction) = search.hit {
+            self.rewind_to(instruction);
+            return true;
+        }
+        if search.index == 0 {
+            self.restore_snapshot(0);
+            self.log.push("No breakpoint hit in the history".to_string());
+            return true;
+        }
+        search.end = self.history.get(search.index).instructions;
+        search.index -= 1;
+        self.restore_snapshot(search.index);
+        self.reverse_search = Some(search);
+        false
+    }
+
+    fn cancel_reverse_continue(&mut self) {
+        if let Some(search) = self.reverse_search.take() {
+            self.rewind_to(search.origin);
+        }
+        self.pause();
+    }
+
+    fn run_until_reached(&mut self, new_instruction: bool, previous_pc: u16) -> bool {
+        let run_until = self.run_until;
+        let cpu = self.lynx.mikey().cpu();
+        match run_until {
+            None => false,
+            Some(RunUntil::Tick(tick)) => self.lynx.ticks() >= tick,
+            Some(_) if !new_instruction => false,
+            // Deeper recursion or an interrupt handler reaching the same address runs with a lower S.
+            Some(RunUntil::Return { pc, s }) => cpu.last_ir_pc == pc && cpu.s() >= s,
+            // Nested calls return with S still at or below the level we started from, an interrupt taken
+            // after the routine pulled bytes returns above it but its RTI isn't the one we're waiting for.
+            Some(RunUntil::StackAbove { s, interrupts }) => {
+                let opcode = self.lynx.cpu_mem(previous_pc);
+                let entered = self.current_pc == self.irq_handler();
+                // A return completed right before an interrupt is hidden by the handler's pushes.
+                let level = if entered { cpu.s().wrapping_add(3) } else { cpu.s() };
+                let returned = interrupts == 0 && matches!(opcode, RTS | RTI) && level > s;
+                // An interrupt can be taken right after the RTI of the previous one.
+                let interrupts = interrupts - (opcode == RTI && interrupts > 0) as u32 + entered as u32;
+                self.run_until = Some(RunUntil::StackAbove { s, interrupts });
+                returned
+            }
+            Some(RunUntil::Frame(frame)) => self.frames >= frame,
+            Some(RunUntil::Instruction(count)) => self.instructions >= count,
+        }
+    }
+
+    fn run_to_reached(&mut self, new_instruction: bool) -> bool {
+        if !new_instruction || self.run_to != Some(self.current_pc) {
+            return false;
+        }
+        self.run_to = None;
+        true
+    }
+
     pub fn handle_inputs(&mut self, ctx: &egui::Context) {
         let j = self.joystick;
         let s = self.switches;                        
@@ -381,8 +1140,28 @@ impl LynxSession {
         if self.switches != s {
             self.lynx.set_switches_u8(self.switches.bits());
         }
+        if self.joystick != j || self.switches != s {
+            self.history.record_input(InputEvent {
+                tick: self.lynx.ticks(),
+                joystick: self.joystick.bits(),
+                switches: self.switches.bits(),
+            });
+        }
     }
     
+    /// Saves the breakpoints, watches, watchpoints and labels, unless the project couldn't be loaded.
+    fn store_project(&self) {
+        let Some(name) = &self.project_name else {
+            return;
+        };
+        ProjectRef {
+            watches: &self.watches,
+            breakpoints: &self.breakpoints,
+            watchpoints: &self.watchpoints,
+            labels: &self.labels,
+        }.store(name);
+    }
+
     fn initialize_core(&mut self, comlynx: &ComlynxCable, cart: PathBuf, settings: &Settings) -> Result<(), std::io::Error> {
         if let Some(path) = settings.boot_rom_path() {
             if self.lynx.load_rom_from_slice(&std::fs::read(path).unwrap()).is_err() {
@@ -390,18 +1169,35 @@ impl LynxSession {
             }
         };
 
-        if self.lynx.load_cart_from_slice(&std::fs::read(cart.to_str().unwrap()).unwrap()).is_err() {
+        let cart_data = std::fs::read(cart.to_str().unwrap()).unwrap();
+        if self.lynx.load_cart_from_slice(&cart_data).is_err() {
             return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Couldn't not load Cartridge file."))
         }
 
-        self.lynx.reset();
+        let project_name = Project::name(&cart_data);
+        if let Some(project) = Project::load(&project_name) {
+            self.watches = project.watches;
+            self.breakpoints = project.breakpoints;
+            self.watchpoints = project.watchpoints;
+            self.labels = project.labels;
+            self.disassembler.set_labels(&self.labels);
+            self.project_name = Some(project_name);
+        }
+
+        self.reset_lynx();
 
         self.rotation = self.lynx.rotation();
         self.cartridge = Some(cart);
 
         self.lynx.set_comlynx_cable(comlynx);
+        self.restart_counters();
 
         Ok(())
     }
 }
 
+impl Drop for LynxSession {
+    fn drop(&mut self) {
+        self.store_project();
+    }
+}
@@ -1,4 +1,4 @@
-use egui::{RichText, TextWrapMode, Vec2};
+use egui::{DragValue, RichText, TextWrapMode, Vec2};
 use holani::consts::{AUD0VOL, TIM0BKUP};
 
 pub struct Timers {
@@ -10,7 +10,24 @@ impl Timers {
         }
     }
 
-    pub fn show(&mut self, timers: &holani::mikey::timers::Timers, ui: &mut egui::Ui) {
+    /// With `editable`, returns the register address and value the user changed, and true once the edit is done.
+    pub fn show(&mut self, timers: &holani::mikey::timers::Timers, ui: &mut egui::Ui, editable: bool) -> (Option<(u16, u8)>, bool) {
+        let mut poke: Option<(u16, u8)> = None;
+        let mut done = false;
+        let mut cell = |ui: &mut egui::Ui, addr: u16, binary: bool| {
+            let mut v = timers.peek(addr);
+            if !editable {
+                ui.label(if binary { format!("{:08b}", v) } else { format!("{:02X}", v) });
+                return;
+            }
+            let drag = if binary { DragValue::new(&mut v).binary(8, false) } else { DragValue::new(&mut v).hexadecimal(2, false, true) };
+            let response = ui.add(drag);
+            if response.changed() {
+                poke = Some((addr, v));
+            }
+            done |= response.drag_stopped() || response.lost_focus();
+        };
+
         ui.label(RichText::new("Timers").strong());
 
         egui::Grid::new("timers_grid")
@@ -30,10 +47,10 @@ impl Timers {
 
                     for i in 0..=7 {
                         ui.label(format!("{}", i));
-                        ui.label(format!("{:02X}", timers.peek(TIM0BKUP+(i*4))));
-                        ui.label(format!("{:08b}", timers.peek(TIM0BKUP+(i*4)+1)));
-                        ui.label(format!("{:02X}", timers.peek(TIM0BKUP+(i*4)+2)));
-                        ui.label(format!("{:08b}", timers.peek(TIM0BKUP+(i*4)+3)));
+                        cell(ui, TIM0BKUP+(i*4), false);
+                        cell(ui, TIM0BKUP+(i*4)+1, true);
+                        cell(ui, TIM0BKUP+(i*4)+2, false);
+                        cell(ui, TIM0BKUP+(i*4)+3, true);
                         ui.label(match timers.timer_trigger(i as usize) {
                             u64::MAX => "∞".to_string(),
                             v => format!("{}", v),
@@ -65,14 +82,14 @@ impl Timers {
         
                             for i in 0..=3 {
                                 ui.label(format!("{}", i));
-                                ui.label(format!("{:02X}", timers.peek(AUD0VOL+(i*8))));
-                                ui.label(format!("{:08b}", timers.peek(AUD0VOL+(i*8)+1)));
-                                ui.label(format!("{:02X}", timers.peek(AUD0VOL+(i*8)+2)));
-                                ui.label(format!("{:08b}", timers.peek(AUD0VOL+(i*8)+3)));
-                                ui.label(format!("{:02X}", timers.peek(AUD0VOL+(i*8)+4)));
-                                ui.label(format!("{:08b}", timers.peek(AUD0VOL+(i*8)+5)));
-                                ui.label(format!("{:02X}", timers.peek(AUD0VOL+(i*8)+6)));
-                                ui.label(format!("{:08b}", timers.peek(AUD0VOL+(i*8)+7)));
+                                cell(ui, AUD0VOL+(i*8), false);
+                                cell(ui, AUD0VOL+(i*8)+1, true);
+                                cell(ui, AUD0VOL+(i*8)+2, false);
+                                cell(ui, AUD0VOL+(i*8)+3, true);
+                                cell(ui, AUD0VOL+(i*8)+4, false);
+                                cell(ui, AUD0VOL+(i*8)+5, true);
+                                cell(ui, AUD0VOL+(i*8)+6, false);
+                                cell(ui, AUD0VOL+(i*8)+7, true);
                                 ui.label(match timers.timer_trigger(8+i as usize) {
                                     u64::MAX => "∞".to_string(),
                                     v => format!("{}", v),
@@ -80,5 +97,7 @@ impl Timers {
                                 ui.end_row();
                             }
                         });
+
+        (poke, done)
     }
 }
@@ -1,28 +1,444 @@
-use std::sync::Arc;
-use egui::{mutex::RwLock, RichText, ScrollArea, Widget};
-use holani::ram::Ram;
+use std::{collections::VecDeque, fs::File, io::{BufWriter, Write}, ops::RangeInclusive};
+use egui::{pos2, vec2, Color32, DragValue, RichText, ScrollArea, Sense, Stroke, TextEdit};
+use log::error;
+use serde::{Deserialize, Serialize};
 
-use super::hex_input;
+use super::{expression::{Expr, ExpressionContext, Register, SymbolContext}, labels::Label};
+
+/// Longest zero-terminated string shown by a watch.
+const MAX_STRING_LENGTH: u16 = 64;
+const MAX_ARRAY_LENGTH: u16 = 64;
+const GRAPH_WIDTH: f32 = 80.0;
+
+const SUZY_START: u16 = 0xFC00;
+const SUZY_END: u16 = 0xFCFF;
+/// System and audio timers, kept by the timers rather than the Mikey registers.
+const TIMERS_START: u16 = 0xFD00;
+const TIMERS_END: u16 = 0xFD3F;
+const MIKEY_START: u16 = 0xFD40;
+const MIKEY_END: u16 = 0xFDFF;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum WatchType {
+    #[default]
+    U8,
+    S8,
+    U16,
+    S16,
+    /// Little-endian 8.8 fixed point, integer part in the high byte.
+    Fixed88,
+    Bcd,
+    /// Zero-terminated string.
+    Str,
+    /// The value of the expression itself, not memory at that address.
+    Value,
+}
+
+impl WatchType {
+    const ALL: [WatchType; 8] = [WatchType::U8, WatchType::S8, WatchType::U16, WatchType::S16, WatchType::Fixed88, WatchType::Bcd, WatchType::Str, WatchType::Value];
+
+    fn label(&self) -> &'static str {
+        match self {
+            WatchType::U8 => "u8",
+            WatchType::S8 => "s8",
+            WatchType::U16 => "u16",
+            WatchType::S16 => "s16",
+            WatchType::Fixed88 => "8.8",
+            WatchType::Bcd => "bcd",
+            WatchType::Str => "str",
+            WatchType::Value => "value",
+        }
+    }
+
+    fn size(&self) -> u16 {
+        match self {
+            WatchType::U16 | WatchType::S16 | WatchType::Fixed88 => 2,
+            _ => 1,
+        }
+    }
+
+    /// Values an editable watch accepts.
+    fn range(&self) -> RangeInclusive<i64> {
+        match self {
+            WatchType::S8 => i8::MIN as i64..=i8::MAX as i64,
+            WatchType::U16 => 0..=u16::MAX as i64,
+            WatchType::S16 => i16::MIN as i64..=i16::MAX as i64,
+            _ => 0..=u8::MAX as i64,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum WatchFormat {
+    #[default]
+    Hex,
+    Decimal,
+    Binary,
+}
+
+impl WatchFormat {
+    const ALL: [WatchFormat; 3] = [WatchFormat::Hex, WatchFormat::Decimal, WatchFormat::Binary];
+
+    fn label(&self) -> &'static str {
+        match self {
+            WatchFormat::Hex => "hex",
+            WatchFormat::Decimal => "dec",
+            WatchFormat::Binary => "bin",
+        }
+    }
+}
+
+/// Where a watch reads its bytes from, the ones its expression reads included.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum WatchSource {
+    /// The RAM underneath, even where hardware or the boot ROM is mapped.
+    #[default]
+    Ram,
+    /// What the CPU sees with the current memory mapping.
+    Cpu,
+    /// Suzy and Mikey registers, whatever the memory mapping.
+    Hardware,
+}
+
+impl WatchSource {
+    const ALL: [WatchSource; 3] = [WatchSource::Ram, WatchSource::Cpu, WatchSource::Hardware];
+
+    fn label(&self) -> &'static str {
+        match self {
+            WatchSource::Ram => "RAM",
+            WatchSource::Cpu => "CPU",
+            WatchSource::Hardware => "HW",
+        }
+    }
+
+    fn read(&self, ctx: &SymbolContext, addr: u16) -> u8 {
+        match (self, addr) {
+            (WatchSource::Ram, _) => ctx.lynx.ram().get(addr),
+            (WatchSource::Hardware, SUZY_START..=SUZY_END) => ctx.lynx.suzy().registers().data(addr),
+            (WatchSource::Hardware, TIMERS_START..=TIMERS_END) => ctx.lynx.mikey().timers().peek(addr),
+            (WatchSource::Hardware, MIKEY_START..=MIKEY_END) => ctx.lynx.mikey().registers().data(addr),
+            (WatchSource::Cpu | WatchSource::Hardware, _) => ctx.lynx.cpu_mem(addr),
+        }
+    }
+}
+
+/// Expression context whose memory reads, `[addr]` included, go through a watch source.
+struct SourceContext<'a> {
+    symbols: &'a SymbolContext<'a>,
+    source: WatchSource,
+}
+
+impl ExpressionContext for SourceContext<'_> {
+    fn register(&self, register: Register) -> u16 {
+        self.symbols.register(register)
+    }
+
+    fn memory(&self, addr: u16) -> u8 {
+        self.source.read(self.symbols, addr)
+    }
+
+    fn symbol(&self, name: &str) -> Option<u16> {
+        self.symbols.symbol(name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Watch {
+    /// Address of the watched memory, or the watched value for `WatchType::Value`.
+    pub expression: String,
+    /// Parsed by `parse` once the labels are known.
+    #[serde(skip, default = "unparsed")]
+    parsed: Result<Expr, String>,
+    #[serde(default)]
+    pub kind: WatchType,
+    #[serde(default)]
+    pub format: WatchFormat,
+    /// Number of consecutive elements, more than one for arrays.
+    #[serde(default = "one")]
+    pub count: u16,
+    #[serde(default)]
+    pub source: WatchSource,
+    #[serde(default)]
+    pub graph: bool,
+    /// Values of the last frames, oldest first.
+    #[serde(skip)]
+    samples: VecDeque<f64>,
+}
+
+fn unparsed() -> Result<Expr, String> {
+    Err("not parsed".to_string())
+}
+
+fn one() -> u16 {
+    1
+}
+
+impl Watch {
+    pub fn new(expression: String, kind: WatchType, count: u16, source: WatchSource, labels: &[Label]) -> Self {
+        Self {
+            parsed: Expr::parse(&expression, labels),
+            expression,
+            kind,
+            format: WatchFormat::Hex,
+            count: count.max(1),
+            source,
+            graph: false,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Parses the expression again, after the labels changed.
+    pub fn parse(&mut self, labels: &[Label]) {
+        self.parsed = Expr::parse(&self.expression, labels);
+    }
+
+    /// The expression reads memory from the same source as the watched bytes.
+    fn context<'a>(&self, ctx: &'a SymbolContext<'a>) -> SourceContext<'a> {
+        SourceContext { symbols: ctx, source: self.source }
+    }
+
+    fn title(&self) -> String {
+        let source = match self.source {
+            WatchSource::Ram => String::new(),
+            source => format!(" {}", source.label()),
+        };
+        if self.count > 1 && !matches!(self.kind, WatchType::Str | WatchType::Value) {
+            format!("{} {}[{}]{}", self.expression, self.kind.label(), self.count, source)
+        } else {
+            format!("{} {}{}", self.expression, self.kind.label(), source)
+        }
+    }
+
+    /// Evaluates the expression and formats what it designates, or returns the parse error.
+    pub fn value(&self, ctx: &SymbolContext) -> Result<String, String> {
+        let v = self.parsed.as_ref().map_err(|e| e.clone())?.eval(&self.context(ctx));
+        if self.kind == WatchType::Value {
+            let digits = if (-0xFF..=0xFF).contains(&v) { 2 } else { 4 };
+            return Ok(Self::format_int(v, digits, self.format));
+        }
+        Ok(self.memory(v as u16, |addr| self.source.read(ctx, addr)))
+    }
+
+    /// Formats the string or the elements starting at `addr`.
+    fn memory(&self, addr: u16, read: impl Fn(u16) -> u8) -> String {
+        if self.kind == WatchType::Str {
+            let mut s = String::from("\"");
+            for i in 0..MAX_STRING_LENGTH {
+                match read(addr.wrapping_add(i)) {
+                    0 => break,
+                    c if c.is_ascii_graphic() || c == b' ' => s.push(c as char),
+                    _ => s.push('.'),
+                }
+            }
+            s.push('"');
+            return s;
+        }
+
+        (0..self.count)
+            .map(|i| self.element(addr.wrapping_add(i.wrapping_mul(self.kind.size())), &read))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Numeric value of the first element, for the graph and the CSV log.
+    fn sample(&self, ctx: &SymbolContext) -> Option<f64> {
+        let v = self.parsed.as_ref().ok()?.eval(&self.context(ctx));
+        let byte = self.source.read(ctx, v as u16);
+        let word = byte as u16 | ((self.source.read(ctx, (v as u16).wrapping_add(1)) as u16) << 8);
+        Some(match self.kind {
+            WatchType::Value => v as f64,
+            WatchType::U8 => byte as f64,
+            WatchType::S8 => byte as i8 as f64,
+            WatchType::U16 => word as f64,
+            WatchType::S16 => word as i16 as f64,
+            WatchType::Fixed88 => word as i16 as f64 / 256.0,
+            WatchType::Bcd => ((byte >> 4) * 10 + (byte & 0x0F)) as f64,
+            WatchType::Str => return None,
+        })
+    }
+
+    fn graph_ui(&self, ui: &mut egui::Ui, height: f32) {
+        let (rect, response) = ui.allocate_exact_size(vec2(GRAPH_WIDTH, height), Sense::hover());
+        if self.samples.len() < 2 {
+            return;
+        }
+        let min = self.samples.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self.samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = if max > min { max - min } else { 1.0 };
+        let step = rect.width() / (self.samples.len() - 1) as f32;
+        let points = self.samples
+            .iter()
+            .enumerate()
+            .map(|(i, v)| pos2(rect.left() + i as f32 * step, rect.bottom() - ((v - min) / range) as f32 * rect.height()))
+            .collect();
+        ui.painter().add(egui::Shape::line(points, Stroke::new(1.0, ui.visuals().text_color())));
+        response.on_hover_text(format!("min {} max {} last {}", min, max, self.samples.back().unwrap_or(&0.0)));
+    }
+
+    /// Address and value of a single numeric RAM watch, which can be edited in place.
+    fn editable(&self, ctx: &SymbolContext) -> Option<(u16, i64)> {
+        if self.count != 1 || self.source != WatchSource::Ram {
+            return None;
+        }
+        let addr = self.parsed.as_ref().ok()?.eval(&self.context(ctx)) as u16;
+        let byte = self.source.read(ctx, addr);
+        let word = byte as u16 | ((self.source.read(ctx, addr.wrapping_add(1)) as u16) << 8);
+        match self.kind {
+            WatchType::U8 => Some((addr, byte as i64)),
+            WatchType::S8 => Some((addr, byte as i8 as i64)),
+            WatchType::U16 => Some((addr, word as i64)),
+            WatchType::S16 => Some((addr, word as i16 as i64)),
+            _ => None,
+        }
+    }
+
+    fn element(&self, addr: u16, read: impl Fn(u16) -> u8) -> String {
+        let byte = read(addr);
+        let word = byte as u16 | ((read(addr.wrapping_add(1)) as u16) << 8);
+
+        match (self.kind, self.format) {
+            (WatchType::Fixed88, WatchFormat::Hex) => format!("${:02X}.{:02X}", word >> 8, word & 0xFF),
+            (WatchType::Fixed88, WatchFormat::Decimal) => format!("{:.3}", word as i16 as f32 / 256.0),
+            (WatchType::Bcd, WatchFormat::Hex | WatchFormat::Decimal) => format!("{:X}", byte),
+            (WatchType::U8 | WatchType::Bcd, f) => Self::format_int(byte as i64, 2, f),
+            (WatchType::S8, f) => Self::format_int(byte as i8 as i64, 2, f),
+            (WatchType::U16 | WatchType::Fixed88, f) => Self::format_int(word as i64, 4, f),
+            (WatchType::S16, f) => Self::format_int(word as i16 as i64, 4, f),
+            (WatchType::Str | WatchType::Value, _) => unreachable!(),
+        }
+    }
+
+    fn format_int(v: i64, digits: usize, format: WatchFormat) -> String {
+        let sign = if v < 0 { "-" } else { "" };
+        match format {
+            WatchFormat::Hex => format!("{}${:0digits$X}", sign, v.unsigned_abs(), digits = digits),
+            WatchFormat::Decimal => format!("{}", v),
+            WatchFormat::Binary => format!("{}%{:0bits$b}", sign, v.unsigned_abs(), bits = digits * 4),
+        }
+    }
+}
+
+/// What the user did in the watch list.
+#[derive(Default)]
+pub struct WatchesResponse {
+    /// A watch was added, removed or changed.
+    pub changed: bool,
+    /// Address and little-endian bytes of a value being edited.
+    pub edit: Option<(u16, Vec<u8>)>,
+    /// The value edit is over.
+    pub edit_done: bool,
+}
 
 pub struct Watches {
-    input: u16,
-    buffer: Arc<RwLock<String>>
+    input: String,
+    kind: WatchType,
+    count: u16,
+    source: WatchSource,
+    /// Number of frames kept for the graphs.
+    history_length: usize,
+    /// Open log with the watches it was started with, its columns don't follow later changes to the list.
+    csv: Option<(BufWriter<File>, Vec<Watch>)>,
 }
 
 impl Watches {
     pub fn new() -> Self {
         Self {
-            input: 0,
-            buffer: Default::default(),
+            input: String::new(),
+            kind: WatchType::U8,
+            count: 1,
+            source: WatchSource::Ram,
+            history_length: 240,
+            csv: None,
+        }
+    }
+
+    /// Records the value of every watch once per frame, and writes them to the CSV log when one is open.
+    pub fn sample(&mut self, whs: &mut [Watch], ctx: &SymbolContext, frame: u64) {
+        let values: Vec<Option<f64>> = whs.iter().map(|w| w.sample(ctx)).collect();
+        for (watch, v) in whs.iter_mut().zip(&values) {
+            if let Some(v) = v {
+                watch.samples.push_back(*v);
+            }
+            while watch.samples.len() > self.history_length {
+                watch.samples.pop_front();
+            }
+        }
+
+        let Some((csv, logged)) = &mut self.csv else {
+            return;
+        };
+        let row: Vec<String> = logged.iter().map(|w| w.sample(ctx).map_or(String::new(), |v| v.to_string())).collect();
+        if let Err(e) = writeln!(csv, "{},{}", frame, row.join(",")) {
+            error!("Couldn't write watch log. '{}'", e);
+            self.csv = None;
         }
     }
 
-    pub fn show_ui(&mut self, ui: &mut egui::Ui, whs: &mut Vec<u16>, ram: &Ram) {
+    fn start_csv(&mut self, whs: &[Watch]) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_title("Watch log")
+            .save_file() else {
+            return;
+        };
+        let header: Vec<String> = whs.iter().map(|w| format!("\"{}\"", w.expression.replace('"', "\"\""))).collect();
+        match File::create(path).map(BufWriter::new) {
+            Err(e) => error!("Couldn't create watch log. '{}'", e),
+            Ok(mut csv) => match writeln!(csv, "frame,{}", header.join(",")) {
+                Err(e) => error!("Couldn't write watch log. '{}'", e),
+                Ok(_) => self.csv = Some((csv, whs.to_vec())),
+            },
+        }
+    }
+
+    /// Values can only be edited with `editable`.
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, whs: &mut Vec<Watch>, ctx: &SymbolContext, editable: bool) -> WatchesResponse {
+        let mut changed = false;
+
         ui.horizontal(|ui| {
             ui.label(RichText::new("Watch").strong());
-            hex_input::HexInput::new(&mut self.input, self.buffer.clone(), 4).ui(ui);
-            if ui.button("Add").clicked() {
-                whs.push(self.input);
+            ui.add(TextEdit::singleline(&mut self.input).hint_text("$addr or expression").desired_width(100.0));
+            egui::ComboBox::from_id_source("watch_type")
+                .selected_text(self.kind.label())
+                .width(40.0)
+                .show_ui(ui, |ui| {
+                    for kind in WatchType::ALL {
+                        ui.selectable_value(&mut self.kind, kind, kind.label());
+                    }
+                });
+            let is_array = !matches!(self.kind, WatchType::Str | WatchType::Value);
+            if is_array {
+                ui.add(DragValue::new(&mut self.count).prefix("x"));
+            }
+            egui::ComboBox::from_id_source("watch_source")
+                .selected_text(self.source.label())
+                .width(40.0)
+                .show_ui(ui, |ui| {
+                    for source in WatchSource::ALL {
+                        ui.selectable_value(&mut self.source, source, source.label());
+                    }
+                });
+            if ui.button("Add").clicked() && !self.input.trim().is_empty() {
+                let expression = self.input.trim().to_string();
+                // An expression reading memory already is the value, typed reads are for addresses and labels.
+                let (kind, count) = match Expr::parse(&expression, ctx.labels) {
+                    Ok(parsed) if parsed.reads_memory() => (WatchType::Value, 1),
+                    _ if is_array => (self.kind, self.count.clamp(1, MAX_ARRAY_LENGTH)),
+                    _ => (self.kind, 1),
+                };
+                whs.push(Watch::new(expression, kind, count, self.source, ctx.labels));
+                changed = true;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut self.history_length).suffix(" frames graphed"));
+            if self.csv.is_some() {
+                if ui.button("⏹ CSV").on_hover_text("Stop logging the watches").clicked() {
+                    self.csv = None;
+                }
+            } else if ui.button("⏺ CSV").on_hover_text("Log the watches to a CSV file, one line per frame").clicked() {
+                self.start_csv(whs);
             }
         });
 
@@ -34,6 +450,8 @@ impl Watches {
         let row_height = ui.text_style_height(&egui::TextStyle::Body);
 
         let mut to_delete: Option<usize> = None;
+        let mut edit: Option<(u16, Vec<u8>)> = None;
+        let mut edit_done = false;
 
         scroll.show_rows(ui, row_height, whs.len(), |ui, line_range| {
             egui::Grid::new("watch_grid")
@@ -43,14 +461,58 @@ impl Watches {
                     let mut current_line = line_range.start;
 
                     while current_line != line_range.end {
-                        let addr = whs[current_line];
-                        let v = ram.get(addr);
-                        ui.monospace(format!("${:04X}: ${:02X} b{:08b} {}", addr, v, v, v));
-                        
+                        let watch = &mut whs[current_line];
+                        ui.monospace(watch.title());
+
+                        if watch.kind == WatchType::Str {
+                            ui.label("");
+                        } else {
+                            egui::ComboBox::from_id_source(("watch_format", current_line))
+                                .selected_text(watch.format.label())
+                                .width(40.0)
+                                .show_ui(ui, |ui| {
+                                    for format in WatchFormat::ALL {
+                                        changed |= ui.selectable_value(&mut watch.format, format, format.label()).changed();
+                                    }
+                                });
+                        }
+
+                        match (editable.then(|| watch.editable(ctx)).flatten(), watch.value(ctx)) {
+                            (Some((addr, mut v)), _) => {
+                                let drag = match (watch.format, watch.kind) {
+                                    (WatchFormat::Hex, WatchType::U8) => DragValue::new(&mut v).hexadecimal(2, false, true).prefix("$"),
+                                    (WatchFormat::Hex, WatchType::U16) => DragValue::new(&mut v).hexadecimal(4, false, true).prefix("$"),
+                                    _ => DragValue::new(&mut v),
+                                };
+                                let drag_response = ui.add(drag.range(watch.kind.range()));
+                                if drag_response.changed() {
+                                    let size = watch.kind.size() as usize;
+                                    edit = Some((addr, (v as u16).to_le_bytes()[..size].to_vec()));
+                                }
+                                edit_done |= drag_response.drag_stopped() || drag_response.lost_focus();
+                            }
+                            (None, Ok(v)) => { ui.monospace(v); }
+                            (None, Err(e)) => { ui.label(RichText::new(e).color(Color32::RED)); }
+                        }
+
+                        if watch.kind != WatchType::Str {
+                            if ui.selectable_label(watch.graph, "📈").clicked() {
+                                watch.graph = !watch.graph;
+                                changed = true;
+                            }
+                        } else {
+                            ui.label("");
+                        }
+                        if watch.graph {
+                            watch.graph_ui(ui, row_height);
+                        } else {
+                            ui.label("");
+                        }
+
                         if ui.add(egui::Button::new("❌").frame(false)).clicked() {
                             to_delete = Some(current_line);
-                        }  
-                        
+                        }
+
                         ui.end_row();
                         current_line += 1;
                     }
@@ -59,6 +521,70 @@ impl Watches {
 
         if let Some(d) = to_delete {
             whs.remove(d);
+            changed = true;
         }
+
+        WatchesResponse { changed, edit, edit_done }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(kind: WatchType, count: u16, format: WatchFormat) -> Watch {
+        Watch { format, ..Watch::new("$0000".to_string(), kind, count, WatchSource::Ram, &[]) }
+    }
+
+    fn reader(memory: &[u8]) -> impl Fn(u16) -> u8 + '_ {
+        |addr| memory.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn format_int() {
+        assert_eq!(Watch::format_int(0x12, 2, WatchFormat::Hex), "$12");
+        assert_eq!(Watch::format_int(0x1234, 4, WatchFormat::Hex), "$1234");
+        assert_eq!(Watch::format_int(-1, 2, WatchFormat::Hex), "-$01");
+        assert_eq!(Watch::format_int(-300, 4, WatchFormat::Decimal), "-300");
+        assert_eq!(Watch::format_int(5, 2, WatchFormat::Binary), "%00000101");
+        assert_eq!(Watch::format_int(-2, 2, WatchFormat::Binary), "-%00000010");
+    }
+
+    #[test]
+    fn signed_elements() {
+        let memory = [0xFE, 0xFF];
+        assert_eq!(watch(WatchType::U8, 1, WatchFormat::Decimal).element(0, reader(&memory)), "254");
+        assert_eq!(watch(WatchType::S8, 1, WatchFormat::Decimal).element(0, reader(&memory)), "-2");
+        assert_eq!(watch(WatchType::S8, 1, WatchFormat::Hex).element(0, reader(&memory)), "-$02");
+        assert_eq!(watch(WatchType::U16, 1, WatchFormat::Hex).element(0, reader(&memory)), "$FFFE");
+        assert_eq!(watch(WatchType::S16, 1, WatchFormat::Decimal).element(0, reader(&memory)), "-2");
+        assert_eq!(watch(WatchType::S16, 1, WatchFormat::Hex).element(0, reader(&memory)), "-$0002");
+    }
+
+    #[test]
+    fn fixed_and_bcd_elements() {
+        let memory = [0x80, 0x01, 0x80, 0xFF, 0x42];
+        assert_eq!(watch(WatchType::Fixed88, 1, WatchFormat::Hex).element(0, reader(&memory)), "$01.80");
+        assert_eq!(watch(WatchType::Fixed88, 1, WatchFormat::Decimal).element(0, reader(&memory)), "1.500");
+        assert_eq!(watch(WatchType::Fixed88, 1, WatchFormat::Decimal).element(2, reader(&memory)), "-0.500");
+        assert_eq!(watch(WatchType::Fixed88, 1, WatchFormat::Binary).element(0, reader(&memory)), "%0000000110000000");
+        assert_eq!(watch(WatchType::Bcd, 1, WatchFormat::Decimal).element(4, reader(&memory)), "42");
+        assert_eq!(watch(WatchType::Bcd, 1, WatchFormat::Binary).element(4, reader(&memory)), "%01000010");
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(watch(WatchType::Str, 1, WatchFormat::Hex).memory(0, reader(b"Hi there\x01\0rest")), "\"Hi there.\"");
+        let unterminated = [b'A'; 100];
+        let expected = format!("\"{}\"", "A".repeat(MAX_STRING_LENGTH as usize));
+        assert_eq!(watch(WatchType::Str, 1, WatchFormat::Hex).memory(0, reader(&unterminated)), expected);
+    }
+
+    #[test]
+    fn arrays() {
+        let memory = [0x01, 0x02, 0x03, 0x04, 0x34, 0x12];
+        assert_eq!(watch(WatchType::U8, 3, WatchFormat::Hex).memory(0, reader(&memory)), "$01 $02 $03");
+        assert_eq!(watch(WatchType::U16, 3, WatchFormat::Hex).memory(0, reader(&memory)), "$0201 $0403 $1234");
+        assert_eq!(watch(WatchType::S8, 2, WatchFormat::Decimal).memory(4, reader(&memory)), "52 18");
     }
-}
\ No newline at end of file
+}
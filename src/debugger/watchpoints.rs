@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use egui::{mutex::RwLock, Color32, RichText, ScrollArea, Widget};
+use serde::{Deserialize, Serialize};
+
+use super::{disassembler::{hardware_registers, Access, MemoryAccess}, hex_input};
+
+#[derive(Serialize, Deserialize)]
+pub struct Watchpoint {
+    pub enabled: bool,
+    pub start: u16,
+    pub end: u16,
+    pub access: Access,
+    /// Hardware register name, for watchpoints added by name.
+    pub name: Option<String>,
+}
+
+impl Watchpoint {
+    pub fn matches(&self, access: &MemoryAccess) -> bool {
+        self.enabled
+            && access.addresses().any(|addr| (self.start..=self.end).contains(&addr))
+            && (self.access == Access::ReadWrite || access.access == Access::ReadWrite || self.access == access.access)
+    }
+}
+
+pub struct Watchpoints {
+    start: u16,
+    start_buffer: Arc<RwLock<String>>,
+    end: u16,
+    end_buffer: Arc<RwLock<String>>,
+    access: Access,
+    register: Option<(u16, &'static str)>,
+}
+
+impl Watchpoints {
+    pub fn new() -> Self {
+        Self {
+            start: 0,
+            start_buffer: Default::default(),
+            end: 0,
+            end_buffer: Default::default(),
+            access: Access::Write,
+            register: None,
+        }
+    }
+
+    /// `last_hit` is the PC of the instruction that triggered the last stop, with its access.
+    /// Returns true when the watchpoints changed.
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, wps: &mut Vec<Watchpoint>, last_hit: Option<(u16, MemoryAccess)>) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Watchpoints").strong());
+            hex_input::HexInput::new(&mut self.start, self.start_buffer.clone(), 5).ui(ui);
+            ui.label("-");
+            hex_input::HexInput::new(&mut self.end, self.end_buffer.clone(), 6).ui(ui);
+            egui::ComboBox::from_id_source("watchpoint_access")
+                .selected_text(self.access.label())
+                .width(40.0)
+                .show_ui(ui, |ui| {
+                    for access in [Access::Read, Access::Write, Access::ReadWrite] {
+                        ui.selectable_value(&mut self.access, access, access.label());
+                    }
+                });
+            if ui.button("Add").clicked() {
+                wps.push(Watchpoint {
+                    enabled: true,
+                    start: self.start,
+                    end: self.end.max(self.start),
+                    access: self.access,
+                    name: None,
+                });
+                changed = true;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("watchpoint_register")
+                .selected_text(self.register.map_or("Register", |(_, name)| name))
+                .show_ui(ui, |ui| {
+                    for (addr, name) in hardware_registers() {
+                        ui.selectable_value(&mut self.register, Some((addr, name)), name);
+                    }
+                });
+            if let Some((addr, name)) = self.register {
+                if ui.button("Add").clicked() {
+                    wps.push(Watchpoint {
+                        enabled: true,
+                        start: addr,
+                        end: addr,
+                        access: self.access,
+                        name: Some(name.to_string()),
+                    });
+                    changed = true;
+                }
+            }
+        });
+
+        if let Some((pc, hit)) = last_hit {
+            match hit.len {
+                1 => ui.monospace(format!("Last: {} ${:04X} by PC ${:04X}", hit.access.label(), hit.addr, pc)),
+                len => ui.monospace(format!("Last: {} ${:04X}-${:04X} by PC ${:04X}", hit.access.label(), hit.addr, hit.addr.wrapping_add(len - 1), pc)),
+            };
+        }
+
+        let scroll = ScrollArea::vertical()
+            .id_source("watchpoints_scroll")
+            .max_height(f32::INFINITY)
+            .auto_shrink([false, true]);
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+
+        let mut to_delete: Option<usize> = None;
+        let mut switch_status: Option<usize> = None;
+
+        scroll.show_rows(ui, row_height, wps.len(), |ui, line_range| {
+            egui::Grid::new("watchpoint_grid")
+                .striped(true)
+                .spacing(egui::Vec2::new(8.0, ui.style().spacing.item_spacing.y))
+                .show(ui, |ui| {
+                    let mut current_line = line_range.start;
+
+                    while current_line != line_range.end {
+                        let wp = &wps[current_line];
+
+                        let icon = if wp.enabled {RichText::new("⏺").color(Color32::RED)} else {RichText::new("○")};
+
+                        if ui.add(egui::Button::new(icon).frame(false)).clicked() {
+                            switch_status = Some(current_line);
+                        }
+
+                        if let Some(name) = &wp.name {
+                            ui.monospace(format!("{} ${:04X}", name, wp.start));
+                        } else if wp.start == wp.end {
+                            ui.monospace(format!("${:04X}", wp.start));
+                        } else {
+                            ui.monospace(format!("${:04X}-${:04X}", wp.start, wp.end));
+                        }
+                        ui.monospace(wp.access.label());
+
+                        if ui.add(egui::Button::new("❌").frame(false)).clicked() {
+                            to_delete = Some(current_line);
+                        }
+
+                        ui.end_row();
+                        current_line += 1;
+                    }
+                });
+        });
+
+        if let Some(d) = to_delete {
+            wps.remove(d);
+            changed = true;
+        }
+
+        if let Some(i) = switch_status {
+            wps[i].enabled = !wps[i].enabled;
+            changed = true;
+        }
+
+        changed
+    }
+}